@@ -0,0 +1,456 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    account_params, models::BlockHeaderResponseR, AddressData, BalanceData, BlockHash,
+    BlockHeaderResponse, CallerWrapper, GetBlockHeaderSelector, HashString, MoneroResult,
+    RpcParams,
+};
+use jsonrpc_core::types::Params;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use std::{
+    fmt::{self, Debug},
+    iter::once,
+    num::NonZeroU64,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+type Parser<T> = Box<dyn FnOnce(Value) -> anyhow::Result<T> + Send>;
+
+/// Source of the ids telling batches apart, so that an entry is never taken from the results of
+/// another batch.
+static NEXT_BATCH_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Handle on one call of a batch, to get its typed result from [`BatchResults::take`].
+pub struct BatchEntry<T> {
+    batch: u64,
+    index: usize,
+    parse: Parser<T>,
+}
+
+impl<T> Debug for BatchEntry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchEntry")
+            .field("batch", &self.batch)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+/// Results of a batch sent with [`DaemonJsonRpcBatch::send`] or [`WalletBatch::send`].
+#[derive(Debug)]
+pub struct BatchResults {
+    batch: u64,
+    results: Vec<Option<jsonrpc_core::Result<Value>>>,
+}
+
+impl BatchResults {
+    /// Take the result of the given entry. Each entry fails or succeeds on its own, like the
+    /// same call sent outside of a batch.
+    pub fn take<T>(&mut self, entry: BatchEntry<T>) -> anyhow::Result<T> {
+        if entry.batch != self.batch {
+            anyhow::bail!("Entry does not belong to this batch");
+        }
+        let result = self
+            .results
+            .get_mut(entry.index)
+            .and_then(Option::take)
+            .ok_or_else(|| anyhow::Error::msg("Entry does not belong to this batch"))?;
+        (entry.parse)(result?)
+    }
+}
+
+/// Calls queued in a batch, shared by the daemon and wallet batches.
+#[derive(Debug)]
+struct Batch {
+    id: u64,
+    calls: Vec<(&'static str, Params)>,
+}
+
+impl Default for Batch {
+    fn default() -> Self {
+        Self {
+            id: NEXT_BATCH_ID.fetch_add(1, Ordering::Relaxed),
+            calls: Vec::new(),
+        }
+    }
+}
+
+impl Batch {
+    fn push<R, T>(
+        &mut self,
+        method: &'static str,
+        params: RpcParams,
+        map: impl FnOnce(R) -> anyhow::Result<T> + Send + 'static,
+    ) -> BatchEntry<T>
+    where
+        R: DeserializeOwned,
+    {
+        self.calls.push((method, params.into()));
+        BatchEntry {
+            batch: self.id,
+            index: self.calls.len() - 1,
+            parse: Box::new(move |value| map(serde_json::from_value(value)?)),
+        }
+    }
+
+    async fn send(self, inner: &CallerWrapper) -> anyhow::Result<BatchResults> {
+        let results = if self.calls.is_empty() {
+            vec![]
+        } else {
+            inner.0.json_rpc_batch_call(self.calls).await?
+        };
+
+        Ok(BatchResults {
+            batch: self.id,
+            results: results.into_iter().map(Some).collect(),
+        })
+    }
+}
+
+/// Batch of calls to the daemon JSON-RPC, sent in a single request. Created with
+/// [`DaemonJsonRpcClient::batch`](crate::DaemonJsonRpcClient::batch).
+///
+/// With the default transport, calls are sent one after the other to nodes rejecting JSON-RPC
+/// batches, under the limits of their methods. This is always the case with `monerod`, which does
+/// not support batches: they only save round trips with other servers, e.g. proxies.
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// use monero_rpc::{GetBlockHeaderSelector, RpcClientBuilder};
+///
+/// let daemon = RpcClientBuilder::new()
+///     .build("http://node.monerooutreach.org:18081")?
+///     .daemon();
+///
+/// let mut batch = daemon.batch();
+/// let entries = (1..=500)
+///     .map(|height| batch.get_block_header(GetBlockHeaderSelector::Height(height)))
+///     .collect::<Vec<_>>();
+///
+/// let mut results = batch.send().await?;
+/// for entry in entries {
+///     println!("{:?}", results.take(entry)?.hash);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DaemonJsonRpcBatch {
+    inner: CallerWrapper,
+    batch: Batch,
+}
+
+impl DaemonJsonRpcBatch {
+    pub(crate) fn new(inner: CallerWrapper) -> Self {
+        Self {
+            inner,
+            batch: Batch::default(),
+        }
+    }
+
+    /// Number of calls in the batch.
+    pub fn len(&self) -> usize {
+        self.batch.calls.len()
+    }
+
+    /// True if the batch contains no call.
+    pub fn is_empty(&self) -> bool {
+        self.batch.calls.is_empty()
+    }
+
+    /// Queue a call to `get_block_count`, see
+    /// [`DaemonJsonRpcClient::get_block_count`](crate::DaemonJsonRpcClient::get_block_count).
+    pub fn get_block_count(&mut self) -> BatchEntry<NonZeroU64> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            count: NonZeroU64,
+        }
+
+        self.batch.push(
+            "get_block_count",
+            RpcParams::None,
+            |rsp: MoneroResult<Rsp>| Ok(rsp.into_inner().count),
+        )
+    }
+
+    /// Queue a call to `on_get_block_hash`, see
+    /// [`DaemonJsonRpcClient::on_get_block_hash`](crate::DaemonJsonRpcClient::on_get_block_hash).
+    pub fn on_get_block_hash(&mut self, height: u64) -> BatchEntry<BlockHash> {
+        self.batch.push(
+            "on_get_block_hash",
+            RpcParams::array(once(height.into())),
+            move |rsp: HashString<BlockHash>| {
+                // see https://github.com/monero-rs/monero-rpc-rs/issues/58 for rationality
+                if rsp.0 == BlockHash::from_slice(&[0; 32]) {
+                    Err(anyhow::Error::msg(format!(
+                        "Invalid height {} supplied.",
+                        height
+                    )))
+                } else {
+                    Ok(rsp.0)
+                }
+            },
+        )
+    }
+
+    /// Queue a call retrieving a block header, see
+    /// [`DaemonJsonRpcClient::get_block_header`](crate::DaemonJsonRpcClient::get_block_header).
+    pub fn get_block_header(
+        &mut self,
+        selector: GetBlockHeaderSelector,
+    ) -> BatchEntry<BlockHeaderResponse> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            block_header: BlockHeaderResponseR,
+        }
+
+        let (request, params) = selector.into_request();
+        self.batch
+            .push(request, params, |rsp: Rsp| Ok(rsp.block_header.into()))
+    }
+
+    /// Send all the queued calls in a single request.
+    pub async fn send(self) -> anyhow::Result<BatchResults> {
+        self.batch.send(&self.inner).await
+    }
+}
+
+/// Batch of calls to the wallet RPC, sent in a single request. Created with
+/// [`WalletClient::batch`](crate::WalletClient::batch).
+///
+/// With the default transport, calls are sent one after the other to wallets rejecting JSON-RPC
+/// batches, under the limits of their methods. This is always the case with `monero-wallet-rpc`,
+/// which does not support batches.
+#[derive(Debug)]
+pub struct WalletBatch {
+    inner: CallerWrapper,
+    batch: Batch,
+}
+
+impl WalletBatch {
+    pub(crate) fn new(inner: CallerWrapper) -> Self {
+        Self {
+            inner,
+            batch: Batch::default(),
+        }
+    }
+
+    /// Number of calls in the batch.
+    pub fn len(&self) -> usize {
+        self.batch.calls.len()
+    }
+
+    /// True if the batch contains no call.
+    pub fn is_empty(&self) -> bool {
+        self.batch.calls.is_empty()
+    }
+
+    /// Queue a call to `get_balance`, see
+    /// [`WalletClient::get_balance`](crate::WalletClient::get_balance).
+    pub fn get_balance(
+        &mut self,
+        account_index: u32,
+        address_indices: Option<Vec<u32>>,
+    ) -> BatchEntry<BalanceData> {
+        self.batch.push(
+            "get_balance",
+            account_params(account_index, "address_indices", address_indices),
+            Ok,
+        )
+    }
+
+    /// Queue a call to `get_address`, see
+    /// [`WalletClient::get_address`](crate::WalletClient::get_address).
+    pub fn get_address(
+        &mut self,
+        account: u32,
+        addresses: Option<Vec<u32>>,
+    ) -> BatchEntry<AddressData> {
+        self.batch.push(
+            "get_address",
+            account_params(account, "address_index", addresses),
+            Ok,
+        )
+    }
+
+    /// Queue a call to `get_height`, see
+    /// [`WalletClient::get_height`](crate::WalletClient::get_height).
+    pub fn get_height(&mut self) -> BatchEntry<NonZeroU64> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            height: NonZeroU64,
+        }
+
+        self.batch
+            .push("get_height", RpcParams::None, |rsp: Rsp| Ok(rsp.height))
+    }
+
+    /// Send all the queued calls in a single request.
+    pub async fn send(self) -> anyhow::Result<BatchResults> {
+        self.batch.send(&self.inner).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mock::MockServer, GetBlockHeaderSelector, RateLimit, RpcClientBuilder};
+    use monero::Amount;
+    use serde_json::json;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn daemon_batch() {
+        let server = MockServer::start().await.unwrap();
+        server
+            .json_rpc("get_block_count", json!({ "count": 3, "status": "OK" }))
+            .json_rpc_handler("get_block_header_by_height", |params| {
+                let height = serde_json::to_value(params).unwrap()["height"].clone();
+                Ok(json!({
+                    "block_header": {
+                        "block_size": 85,
+                        "block_weight": 85,
+                        "cumulative_difficulty": height,
+                        "depth": 1,
+                        "difficulty": 1,
+                        "hash": format!("{:064x}", height.as_u64().unwrap()),
+                        "height": height,
+                        "long_term_weight": 85,
+                        "major_version": 16,
+                        "miner_tx_hash": format!("{:064x}", 1),
+                        "minor_version": 16,
+                        "nonce": 0,
+                        "num_txes": 0,
+                        "orphan_status": false,
+                        "prev_hash": format!("{:064x}", 0),
+                        "reward": 600000000000u64,
+                        "timestamp": 1700000000,
+                    },
+                }))
+            })
+            .json_rpc_error("on_get_block_hash", -2, "Requested block height too big");
+
+        let daemon = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon();
+
+        let mut batch = daemon.batch();
+        let count = batch.get_block_count();
+        let headers = (1..=2)
+            .map(|height| batch.get_block_header(GetBlockHeaderSelector::Height(height)))
+            .collect::<Vec<_>>();
+        let hash = batch.on_get_block_hash(10);
+        assert_eq!(batch.len(), 4);
+
+        let mut results = batch.send().await.unwrap();
+        assert_eq!(results.take(count).unwrap().get(), 3);
+        for (height, header) in (1..=2).zip(headers) {
+            assert_eq!(results.take(header).unwrap().height, height);
+        }
+        assert_eq!(
+            results.take(hash).unwrap_err().to_string(),
+            "Server error: Requested block height too big"
+        );
+
+        // all the calls are answered by one request
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[1].method, "get_block_header_by_height");
+        assert_eq!(server.http_requests(), 1);
+
+        // an empty batch is not sent
+        assert!(daemon.batch().is_empty());
+        daemon.batch().send().await.unwrap();
+        assert_eq!(server.http_requests(), 1);
+
+        // entries cannot be taken from the results of another batch, even at a valid index
+        let mut batch = daemon.batch();
+        let count = batch.get_block_count();
+        let mut other = daemon.batch();
+        other.get_block_count();
+        let mut results = other.send().await.unwrap();
+        assert_eq!(
+            results.take(count).unwrap_err().to_string(),
+            "Entry does not belong to this batch"
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_fallback_is_limited() {
+        let server = MockServer::start().await.unwrap();
+        server
+            .json_rpc("get_block_count", json!({ "count": 3, "status": "OK" }))
+            .reject_batches(true);
+
+        // 1 request every 10ms
+        let daemon = RpcClientBuilder::new()
+            .max_in_flight(1)
+            .rate_limit(RateLimit::new(10, Duration::from_millis(100)).burst(1))
+            .build(server.url())
+            .unwrap()
+            .daemon();
+
+        let mut batch = daemon.batch();
+        let entries = (0..6).map(|_| batch.get_block_count()).collect::<Vec<_>>();
+        let start = Instant::now();
+        let mut results = batch.send().await.unwrap();
+
+        // the rejected batch and each of its calls take a token
+        assert!(start.elapsed() >= Duration::from_millis(55));
+        assert_eq!(server.requests().len(), 6);
+        assert_eq!(server.http_requests(), 7);
+        for entry in entries {
+            assert_eq!(results.take(entry).unwrap().get(), 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn wallet_batch() {
+        let server = MockServer::start().await.unwrap();
+        server.json_rpc_handler("get_balance", |params| {
+            let account_index = serde_json::to_value(params).unwrap()["account_index"].clone();
+            Ok(json!({
+                "balance": account_index.as_u64().unwrap() * 1000,
+                "unlocked_balance": 0,
+                "multisig_import_needed": false,
+            }))
+        });
+
+        let wallet = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .wallet();
+
+        let mut batch = wallet.batch();
+        let balances = (0..3)
+            .map(|account| batch.get_balance(account, None))
+            .collect::<Vec<_>>();
+        let height = batch.get_height();
+
+        let mut results = batch.send().await.unwrap();
+        for (account, balance) in (0..3).zip(balances) {
+            assert_eq!(
+                results.take(balance).unwrap().balance,
+                Amount::from_pico(account * 1000)
+            );
+        }
+        assert_eq!(
+            results.take(height).unwrap_err().to_string(),
+            "Method not found: Method not found"
+        );
+    }
+}
@@ -0,0 +1,442 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jsonrpc_core::types::Params;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tracing::*;
+
+/// Methods whose responses may be cached, once deep enough below the tip.
+const CACHEABLE_METHODS: &[&str] = &[
+    "on_get_block_hash",
+    "get_block_header_by_hash",
+    "get_block_header_by_height",
+    "get_block_headers_range",
+    "get_block",
+    "get_transactions",
+];
+
+/// Key of a cached response: the method and its serialized parameters.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct CacheKey {
+    /// Name of the method, e.g. `get_block_header_by_height`.
+    pub method: String,
+    /// Parameters of the call, serialized as JSON.
+    pub params: String,
+}
+
+/// Store of daemon responses, see [`RpcClientBuilder::cache`].
+///
+/// [`LruCache`] is used by default. Implement this trait to keep responses elsewhere, e.g. on
+/// disk to share them between runs.
+///
+/// [`RpcClientBuilder::cache`]: crate::RpcClientBuilder::cache
+pub trait ResponseCache: Debug + Send + Sync {
+    /// Return the response stored for `key`, if any.
+    fn get(&self, key: &CacheKey) -> Option<Value>;
+
+    /// Store the response for `key`.
+    fn insert(&self, key: CacheKey, response: Value);
+}
+
+/// In-memory [`ResponseCache`] evicting the least recently used responses.
+#[derive(Debug)]
+pub struct LruCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    tick: u64,
+    entries: HashMap<CacheKey, (Value, u64)>,
+    by_use: BTreeMap<u64, CacheKey>,
+}
+
+impl LruCache {
+    /// Creates a cache holding up to `capacity` responses.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    /// Number of responses in the cache.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// True if the cache holds no response.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl LruState {
+    fn touch(&mut self, key: &CacheKey) -> u64 {
+        self.tick += 1;
+        if let Some((_, last_use)) = self.entries.get_mut(key) {
+            self.by_use.remove(last_use);
+            *last_use = self.tick;
+        }
+        self.by_use.insert(self.tick, key.clone());
+        self.tick
+    }
+}
+
+impl ResponseCache for LruCache {
+    fn get(&self, key: &CacheKey) -> Option<Value> {
+        let mut state = self.state.lock().unwrap();
+        state.entries.get(key)?;
+        state.touch(key);
+        state.entries.get(key).map(|(response, _)| response.clone())
+    }
+
+    fn insert(&self, key: CacheKey, response: Value) {
+        let mut state = self.state.lock().unwrap();
+        let tick = state.touch(&key);
+        state.entries.insert(key, (response, tick));
+
+        while state.entries.len() > self.capacity {
+            let oldest = match state.by_use.pop_first() {
+                Some((_, oldest)) => oldest,
+                None => break,
+            };
+            state.entries.remove(&oldest);
+        }
+    }
+}
+
+/// Caching rules on top of a [`ResponseCache`]: only responses about blocks at least
+/// `min_depth` blocks below the tip are stored, tip-sensitive calls always go to the network.
+#[derive(Debug)]
+pub(crate) struct CacheLayer {
+    store: Arc<dyn ResponseCache>,
+    min_depth: u64,
+    /// Highest tip height seen in responses, 0 if unknown.
+    tip: AtomicU64,
+}
+
+impl CacheLayer {
+    pub(crate) fn new(store: Arc<dyn ResponseCache>, min_depth: u64) -> Self {
+        Self {
+            store,
+            min_depth,
+            tip: AtomicU64::new(0),
+        }
+    }
+
+    fn key(method: &str, params: &Params) -> Option<CacheKey> {
+        if !CACHEABLE_METHODS.contains(&method) {
+            return None;
+        }
+        Some(CacheKey {
+            method: method.to_string(),
+            params: serde_json::to_string(params).ok()?,
+        })
+    }
+
+    /// Cached response of the call, if any, with its depths and confirmations counted from the
+    /// known tip rather than from the tip at the time it was stored.
+    pub(crate) fn get(&self, method: &str, params: &Params) -> Option<Value> {
+        let mut response = self.store.get(&Self::key(method, params)?)?;
+        trace!("Cache hit for {} with params {:?}", method, params);
+        self.refresh(&mut response);
+        Some(response)
+    }
+
+    /// Rewrite the tip-relative fields of a response: `depth` of block headers and
+    /// `confirmations` of transactions.
+    fn refresh(&self, response: &mut Value) {
+        let refresh_header = |header: &mut Value| {
+            let depth = header
+                .get("height")
+                .and_then(Value::as_u64)
+                .and_then(|height| self.depth(height));
+            if let (Some(depth), Some(field)) = (depth, header.get_mut("depth")) {
+                *field = depth.into();
+            }
+        };
+
+        if let Some(header) = response.get_mut("block_header") {
+            refresh_header(header);
+        }
+        if let Some(headers) = response.get_mut("headers").and_then(Value::as_array_mut) {
+            headers.iter_mut().for_each(refresh_header);
+        }
+        if let Some(txs) = response.get_mut("txs").and_then(Value::as_array_mut) {
+            for tx in txs {
+                let depth = tx
+                    .get("block_height")
+                    .and_then(Value::as_u64)
+                    .and_then(|height| self.depth(height));
+                if let (Some(depth), Some(field)) = (depth, tx.get_mut("confirmations")) {
+                    // the block holding the transaction counts as one confirmation
+                    *field = (depth + 1).into();
+                }
+            }
+        }
+    }
+
+    /// Learn the tip from a response, and store it if it is deep enough below the tip.
+    pub(crate) fn store(&self, method: &str, params: &Params, response: &Value) {
+        self.observe_tip(method, response);
+
+        if let Some(key) = Self::key(method, params) {
+            if self.is_final(method, params, response) {
+                self.store.insert(key, response.clone());
+            }
+        }
+    }
+
+    fn observe_tip(&self, method: &str, response: &Value) {
+        let from_header =
+            |header: &Value| Some(header.get("height")?.as_u64()? + header.get("depth")?.as_u64()?);
+
+        let tip = match method {
+            "get_block_count" => response
+                .get("count")
+                .and_then(Value::as_u64)
+                .map(|count| count.saturating_sub(1)),
+            _ => response
+                .get("block_header")
+                .and_then(from_header)
+                .or_else(|| {
+                    response
+                        .get("headers")?
+                        .as_array()?
+                        .iter()
+                        .filter_map(from_header)
+                        .max()
+                }),
+        };
+
+        if let Some(tip) = tip {
+            self.tip.fetch_max(tip, Ordering::Relaxed);
+        }
+    }
+
+    /// Depth of a block below the known tip, `None` if the tip is unknown.
+    fn depth(&self, height: u64) -> Option<u64> {
+        match self.tip.load(Ordering::Relaxed) {
+            0 => None,
+            tip => Some(tip.saturating_sub(height)),
+        }
+    }
+
+    fn is_final(&self, method: &str, params: &Params, response: &Value) -> bool {
+        let deep_enough = |depth: Option<u64>| depth.map_or(false, |depth| depth >= self.min_depth);
+        let header_depth = |header: &Value| header.get("depth").and_then(Value::as_u64);
+
+        match method {
+            "on_get_block_hash" => {
+                let height = match params {
+                    Params::Array(params) => params.first().and_then(Value::as_u64),
+                    _ => None,
+                };
+                // a hash of zeros is returned for heights above the tip
+                let is_zero = response
+                    .as_str()
+                    .map_or(true, |hash| hash.chars().all(|c| c == '0'));
+                !is_zero && deep_enough(height.and_then(|height| self.depth(height)))
+            }
+            "get_block_header_by_hash" | "get_block_header_by_height" | "get_block" => {
+                deep_enough(response.get("block_header").and_then(header_depth))
+            }
+            "get_block_headers_range" => {
+                response
+                    .get("headers")
+                    .and_then(Value::as_array)
+                    .map_or(false, |headers| {
+                        headers
+                            .iter()
+                            .all(|header| deep_enough(header_depth(header)))
+                    })
+            }
+            "get_transactions" => {
+                let missed = response
+                    .get("missed_tx")
+                    .and_then(Value::as_array)
+                    .map_or(false, |missed| !missed.is_empty());
+                let txs = response.get("txs").and_then(Value::as_array);
+                response.get("status").and_then(Value::as_str) == Some("OK")
+                    && !missed
+                    && txs.map_or(false, |txs| {
+                        !txs.is_empty()
+                            && txs.iter().all(|tx| {
+                                tx.get("in_pool").and_then(Value::as_bool) == Some(false)
+                                    && deep_enough(
+                                        tx.get("block_height")
+                                            .and_then(Value::as_u64)
+                                            .and_then(|height| self.depth(height)),
+                                    )
+                            })
+                    })
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(method: &str) -> CacheKey {
+        CacheKey {
+            method: method.to_string(),
+            params: String::new(),
+        }
+    }
+
+    #[test]
+    fn lru_cache_evicts_least_recently_used() {
+        let cache = LruCache::new(2);
+        cache.insert(key("a"), json!(1));
+        cache.insert(key("b"), json!(2));
+        assert_eq!(cache.get(&key("a")), Some(json!(1)));
+
+        cache.insert(key("c"), json!(3));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key("b")), None);
+        assert_eq!(cache.get(&key("a")), Some(json!(1)));
+        assert_eq!(cache.get(&key("c")), Some(json!(3)));
+
+        cache.insert(key("a"), json!(4));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key("a")), Some(json!(4)));
+    }
+
+    #[test]
+    fn cache_layer_depth_rules() {
+        let layer = CacheLayer::new(Arc::new(LruCache::new(100)), 10);
+        let header = |height: u64, depth: u64| json!({ "block_header": { "height": height, "depth": depth }, "status": "OK" });
+        let by_height = |height: u64| {
+            Params::Map(
+                [("height".to_string(), json!(height))]
+                    .into_iter()
+                    .collect(),
+            )
+        };
+
+        // the tip is unknown, block hashes are not cached
+        let hash = json!(format!("{:064x}", 1));
+        let params = Params::Array(vec![json!(5)]);
+        layer.store("on_get_block_hash", &params, &hash);
+        assert_eq!(layer.get("on_get_block_hash", &params), None);
+
+        // headers carry their depth, and teach the tip
+        layer.store("get_block_header_by_height", &by_height(5), &header(5, 95));
+        layer.store("get_block_header_by_height", &by_height(95), &header(95, 5));
+        assert_eq!(
+            layer.get("get_block_header_by_height", &by_height(5)),
+            Some(header(5, 95))
+        );
+        assert_eq!(
+            layer.get("get_block_header_by_height", &by_height(95)),
+            None
+        );
+
+        layer.store("on_get_block_hash", &params, &hash);
+        assert_eq!(layer.get("on_get_block_hash", &params), Some(hash));
+        let params = Params::Array(vec![json!(1000)]);
+        layer.store("on_get_block_hash", &params, &json!(format!("{:064x}", 0)));
+        assert_eq!(layer.get("on_get_block_hash", &params), None);
+
+        // tip-sensitive calls are never cached
+        layer.store("get_last_block_header", &Params::None, &header(100, 0));
+        layer.store("get_block_count", &Params::None, &json!({ "count": 200 }));
+        assert_eq!(layer.get("get_last_block_header", &Params::None), None);
+        assert_eq!(layer.get("get_block_count", &Params::None), None);
+
+        // confirmed transactions are cached, transactions in the pool are not
+        let tx = |in_pool: bool, block_height: u64| {
+            json!({
+                "status": "OK",
+                "txs": [{ "in_pool": in_pool, "block_height": block_height }],
+            })
+        };
+        let params = Params::Map(
+            [("txs_hashes".to_string(), json!(["aa"]))]
+                .into_iter()
+                .collect(),
+        );
+        layer.store("get_transactions", &params, &tx(true, 0));
+        assert_eq!(layer.get("get_transactions", &params), None);
+        layer.store("get_transactions", &params, &tx(false, 195));
+        assert_eq!(layer.get("get_transactions", &params), None);
+        layer.store("get_transactions", &params, &tx(false, 150));
+        assert_eq!(layer.get("get_transactions", &params), Some(tx(false, 150)));
+    }
+
+    #[test]
+    fn cache_layer_refreshes_depth_from_tip() {
+        let layer = CacheLayer::new(Arc::new(LruCache::new(100)), 10);
+        let header = |height: u64, depth: u64| json!({ "height": height, "depth": depth });
+        let by_height = Params::Map([("height".to_string(), json!(5))].into_iter().collect());
+        let range = Params::Map(
+            [
+                ("start_height".to_string(), json!(5)),
+                ("end_height".to_string(), json!(6)),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let txs = Params::Map(
+            [("txs_hashes".to_string(), json!(["aa"]))]
+                .into_iter()
+                .collect(),
+        );
+        let tx = |confirmations: u64| {
+            json!({
+                "status": "OK",
+                "txs": [{ "in_pool": false, "block_height": 5, "confirmations": confirmations }],
+            })
+        };
+
+        layer.store(
+            "get_block_header_by_height",
+            &by_height,
+            &json!({ "block_header": header(5, 95), "status": "OK" }),
+        );
+        layer.store(
+            "get_block_headers_range",
+            &range,
+            &json!({ "headers": [header(5, 95), header(6, 94)], "status": "OK" }),
+        );
+        layer.store("get_transactions", &txs, &tx(96));
+
+        // the tip advances by 20 blocks between the store and the hit
+        layer.store("get_block_count", &Params::None, &json!({ "count": 121 }));
+
+        assert_eq!(
+            layer.get("get_block_header_by_height", &by_height),
+            Some(json!({ "block_header": header(5, 115), "status": "OK" }))
+        );
+        assert_eq!(
+            layer.get("get_block_headers_range", &range),
+            Some(json!({ "headers": [header(5, 115), header(6, 114)], "status": "OK" }))
+        );
+        assert_eq!(layer.get("get_transactions", &txs), Some(tx(116)));
+    }
+}
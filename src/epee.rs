@@ -0,0 +1,335 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Epee portable storage, the format of the binary endpoints of the daemon such as
+//! `/get_outs.bin`.
+
+use anyhow::{bail, Context};
+use std::convert::TryInto;
+
+/// Signatures and version opening every message.
+const HEADER: [u8; 9] = [0x01, 0x11, 0x01, 0x01, 0x01, 0x01, 0x02, 0x01, 0x01];
+
+const TYPE_INT64: u8 = 1;
+const TYPE_INT32: u8 = 2;
+const TYPE_INT16: u8 = 3;
+const TYPE_INT8: u8 = 4;
+const TYPE_UINT64: u8 = 5;
+const TYPE_UINT32: u8 = 6;
+const TYPE_UINT16: u8 = 7;
+const TYPE_UINT8: u8 = 8;
+const TYPE_DOUBLE: u8 = 9;
+const TYPE_STRING: u8 = 10;
+const TYPE_BOOL: u8 = 11;
+const TYPE_OBJECT: u8 = 12;
+const FLAG_ARRAY: u8 = 0x80;
+
+/// Maximum nesting of objects and arrays accepted when decoding.
+const MAX_DEPTH: usize = 32;
+
+/// Value of a section entry.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Int(i64),
+    UInt(u64),
+    Double(f64),
+    /// Strings are raw bytes, and also carry binary data such as keys and hashes.
+    Blob(Vec<u8>),
+    Bool(bool),
+    Object(Section),
+    Array(Vec<Value>),
+}
+
+/// Named entries of an object, and of the root of a message.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Section(pub Vec<(String, Value)>);
+
+impl Section {
+    pub(crate) fn get(&self, name: &str) -> Option<&Value> {
+        self.0
+            .iter()
+            .find(|(entry, _)| entry == name)
+            .map(|(_, value)| value)
+    }
+
+    fn field(&self, name: &str) -> anyhow::Result<&Value> {
+        self.get(name)
+            .with_context(|| format!("Missing field `{}`", name))
+    }
+
+    pub(crate) fn u64(&self, name: &str) -> anyhow::Result<u64> {
+        match self.field(name)? {
+            Value::UInt(v) => Ok(*v),
+            Value::Int(v) => Ok((*v).try_into()?),
+            _ => bail!("Field `{}` is not an integer", name),
+        }
+    }
+
+    pub(crate) fn bool(&self, name: &str) -> anyhow::Result<bool> {
+        match self.field(name)? {
+            Value::Bool(v) => Ok(*v),
+            _ => bail!("Field `{}` is not a boolean", name),
+        }
+    }
+
+    pub(crate) fn blob(&self, name: &str) -> anyhow::Result<&[u8]> {
+        match self.field(name)? {
+            Value::Blob(v) => Ok(v),
+            _ => bail!("Field `{}` is not a string", name),
+        }
+    }
+
+    /// Objects of an array, empty if the field is missing since empty arrays are not sent.
+    pub(crate) fn objects(&self, name: &str) -> anyhow::Result<Vec<&Section>> {
+        match self.get(name) {
+            None => Ok(vec![]),
+            Some(Value::Array(values)) => values
+                .iter()
+                .map(|value| match value {
+                    Value::Object(section) => Ok(section),
+                    _ => bail!("Field `{}` is not an array of objects", name),
+                })
+                .collect(),
+            Some(_) => bail!("Field `{}` is not an array", name),
+        }
+    }
+}
+
+/// Encode a message.
+pub(crate) fn to_bytes(root: &Section) -> anyhow::Result<Vec<u8>> {
+    let mut out = HEADER.to_vec();
+    write_section(&mut out, root)?;
+    Ok(out)
+}
+
+/// Decode a message.
+pub(crate) fn from_bytes(bytes: &[u8]) -> anyhow::Result<Section> {
+    let mut reader = Reader(bytes);
+    if reader.take(HEADER.len())? != HEADER {
+        bail!("Invalid portable storage header");
+    }
+    let root = reader.section(0)?;
+    if !reader.0.is_empty() {
+        bail!("{} trailing bytes after portable storage", reader.0.len());
+    }
+    Ok(root)
+}
+
+fn write_varint(out: &mut Vec<u8>, v: u64) -> anyhow::Result<()> {
+    match v {
+        0..=0x3f => out.push((v << 2) as u8),
+        0x40..=0x3fff => out.extend_from_slice(&((v << 2) as u16 | 1).to_le_bytes()),
+        0x4000..=0x3fff_ffff => out.extend_from_slice(&((v << 2) as u32 | 2).to_le_bytes()),
+        0x4000_0000..=0x3fff_ffff_ffff_ffff => out.extend_from_slice(&((v << 2) | 3).to_le_bytes()),
+        _ => bail!("Size {} too large for portable storage", v),
+    }
+    Ok(())
+}
+
+fn write_section(out: &mut Vec<u8>, section: &Section) -> anyhow::Result<()> {
+    // empty arrays are left out, as the daemon does
+    let entries = section
+        .0
+        .iter()
+        .filter(|(_, value)| !matches!(value, Value::Array(values) if values.is_empty()))
+        .collect::<Vec<_>>();
+
+    write_varint(out, entries.len() as u64)?;
+    for (name, value) in entries {
+        let name_len: u8 = name
+            .len()
+            .try_into()
+            .with_context(|| format!("Field name `{}` too long", name))?;
+        out.push(name_len);
+        out.extend_from_slice(name.as_bytes());
+
+        match value {
+            Value::Array(values) => {
+                let element_type = type_of(&values[0]);
+                if values.iter().any(|value| type_of(value) != element_type) {
+                    bail!("Array `{}` mixes types", name);
+                }
+                out.push(FLAG_ARRAY | element_type);
+                write_varint(out, values.len() as u64)?;
+                for value in values {
+                    write_value(out, value)?;
+                }
+            }
+            value => {
+                out.push(type_of(value));
+                write_value(out, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn type_of(value: &Value) -> u8 {
+    match value {
+        Value::Int(_) => TYPE_INT64,
+        Value::UInt(_) => TYPE_UINT64,
+        Value::Double(_) => TYPE_DOUBLE,
+        Value::Blob(_) => TYPE_STRING,
+        Value::Bool(_) => TYPE_BOOL,
+        Value::Object(_) => TYPE_OBJECT,
+        Value::Array(_) => FLAG_ARRAY,
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) -> anyhow::Result<()> {
+    match value {
+        Value::Int(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::UInt(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::Double(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::Blob(v) => {
+            write_varint(out, v.len() as u64)?;
+            out.extend_from_slice(v);
+        }
+        Value::Bool(v) => out.push(u8::from(*v)),
+        Value::Object(section) => write_section(out, section)?,
+        Value::Array(_) => bail!("Nested arrays are not supported"),
+    }
+    Ok(())
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        if self.0.len() < len {
+            bail!("Truncated portable storage");
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn varint(&mut self) -> anyhow::Result<usize> {
+        let first = *self.0.first().context("Truncated portable storage")?;
+        let v = match first & 0x03 {
+            0 => u64::from(self.array::<1>()?[0]),
+            1 => u64::from(u16::from_le_bytes(self.array()?)),
+            2 => u64::from(u32::from_le_bytes(self.array()?)),
+            _ => u64::from_le_bytes(self.array()?),
+        };
+        Ok((v >> 2).try_into()?)
+    }
+
+    fn section(&mut self, depth: usize) -> anyhow::Result<Section> {
+        if depth > MAX_DEPTH {
+            bail!("Portable storage nested too deep");
+        }
+
+        let count = self.varint()?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let name_len = self.array::<1>()?[0];
+            let name = String::from_utf8(self.take(name_len.into())?.to_vec())?;
+            let ty = self.array::<1>()?[0];
+
+            let value = if ty & FLAG_ARRAY != 0 {
+                let len = self.varint()?;
+                let mut values = Vec::new();
+                for _ in 0..len {
+                    values.push(self.value(ty & !FLAG_ARRAY, depth)?);
+                }
+                Value::Array(values)
+            } else {
+                self.value(ty, depth)?
+            };
+            entries.push((name, value));
+        }
+        Ok(Section(entries))
+    }
+
+    fn value(&mut self, ty: u8, depth: usize) -> anyhow::Result<Value> {
+        Ok(match ty {
+            TYPE_INT64 => Value::Int(i64::from_le_bytes(self.array()?)),
+            TYPE_INT32 => Value::Int(i32::from_le_bytes(self.array()?).into()),
+            TYPE_INT16 => Value::Int(i16::from_le_bytes(self.array()?).into()),
+            TYPE_INT8 => Value::Int(i8::from_le_bytes(self.array()?).into()),
+            TYPE_UINT64 => Value::UInt(u64::from_le_bytes(self.array()?)),
+            TYPE_UINT32 => Value::UInt(u32::from_le_bytes(self.array()?).into()),
+            TYPE_UINT16 => Value::UInt(u16::from_le_bytes(self.array()?).into()),
+            TYPE_UINT8 => Value::UInt(self.array::<1>()?[0].into()),
+            TYPE_DOUBLE => Value::Double(f64::from_le_bytes(self.array()?)),
+            TYPE_STRING => {
+                let len = self.varint()?;
+                Value::Blob(self.take(len)?.to_vec())
+            }
+            TYPE_BOOL => Value::Bool(self.array::<1>()?[0] != 0),
+            TYPE_OBJECT => Value::Object(self.section(depth + 1)?),
+            _ => bail!("Unsupported portable storage type {}", ty),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint() {
+        for v in [0, 0x3f, 0x40, 0x3fff, 0x4000, 0x3fff_ffff, 0x4000_0000] {
+            let mut out = vec![];
+            write_varint(&mut out, v).unwrap();
+            assert_eq!(Reader(&out).varint().unwrap() as u64, v);
+        }
+
+        let mut out = vec![];
+        write_varint(&mut out, 300).unwrap();
+        assert_eq!(out, vec![0xb1, 0x04]);
+        assert!(write_varint(&mut out, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let output = |index: u64| {
+            Value::Object(Section(vec![
+                ("amount".to_string(), Value::UInt(0)),
+                ("index".to_string(), Value::UInt(index)),
+            ]))
+        };
+        let root = Section(vec![
+            (
+                "outputs".to_string(),
+                Value::Array(vec![output(1), output(2)]),
+            ),
+            ("get_txid".to_string(), Value::Bool(true)),
+            ("empty".to_string(), Value::Array(vec![])),
+            ("status".to_string(), Value::Blob(b"OK".to_vec())),
+        ]);
+
+        let bytes = to_bytes(&root).unwrap();
+        assert_eq!(&bytes[..9], &HEADER);
+        // root section of 3 entries, the empty array is left out
+        assert_eq!(bytes[9], 3 << 2);
+
+        let decoded = from_bytes(&bytes).unwrap();
+        let outputs = decoded.objects("outputs").unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[1].u64("index").unwrap(), 2);
+        assert!(decoded.bool("get_txid").unwrap());
+        assert_eq!(decoded.blob("status").unwrap(), b"OK");
+        assert!(decoded.objects("empty").unwrap().is_empty());
+        assert!(decoded.u64("status").is_err());
+
+        assert!(from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(from_bytes(&[0; 10]).is_err());
+    }
+}
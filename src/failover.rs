@@ -0,0 +1,349 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::retry::{may_resend, ErrorClass};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+use tracing::*;
+
+/// Snapshot of the state of one node of an [`RpcClient`](crate::RpcClient).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NodeState {
+    /// Address of the node.
+    pub address: String,
+    /// False if the last call or health check on this node failed or returned `BUSY`.
+    pub healthy: bool,
+    /// True if this node is the one currently preferred for calls.
+    pub active: bool,
+    /// Height of the node, as returned by the last health check.
+    pub height: Option<u64>,
+    /// RPC version of the node, as returned by the last health check.
+    pub version: Option<(u16, u16)>,
+    /// Number of failures since the last successful call on this node.
+    pub consecutive_failures: u32,
+    /// Description of the last failure on this node, if any.
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug)]
+struct Node {
+    address: String,
+    state: Mutex<NodeHealth>,
+}
+
+#[derive(Clone, Debug)]
+struct NodeHealth {
+    healthy: bool,
+    height: Option<u64>,
+    version: Option<(u16, u16)>,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+}
+
+/// Outcome of a call on a single node, used to decide whether to fail over to the next node.
+pub(crate) trait NodeResponse {
+    /// True if the node answered that it is busy, e.g. because it is still syncing.
+    fn is_busy(&self) -> bool;
+}
+
+/// Set of nodes behind a `RemoteCaller`. A pool of a single node behaves
+/// exactly like a plain client: results and errors are returned untouched.
+#[derive(Debug)]
+pub(crate) struct NodePool {
+    nodes: Vec<Node>,
+    active: AtomicUsize,
+    prefer_highest_height: bool,
+}
+
+impl NodePool {
+    pub(crate) fn new(addresses: Vec<String>, prefer_highest_height: bool) -> anyhow::Result<Self> {
+        if addresses.is_empty() {
+            return Err(anyhow::Error::msg("At least one node address is required"));
+        }
+
+        Ok(Self {
+            nodes: addresses
+                .into_iter()
+                .map(|address| Node {
+                    address,
+                    state: Mutex::new(NodeHealth {
+                        healthy: true,
+                        height: None,
+                        version: None,
+                        consecutive_failures: 0,
+                        last_error: None,
+                    }),
+                })
+                .collect(),
+            active: AtomicUsize::new(0),
+            prefer_highest_height,
+        })
+    }
+
+    pub(crate) fn addresses(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(|node| node.address.as_str())
+    }
+
+    fn health(&self, index: usize) -> NodeHealth {
+        self.nodes[index].state.lock().unwrap().clone()
+    }
+
+    /// Order in which nodes are tried: healthy nodes first, starting from the active one (or by
+    /// decreasing height if configured so), then unhealthy nodes as a last resort.
+    fn candidates(&self) -> Vec<usize> {
+        let len = self.nodes.len();
+        let active = self.active.load(Ordering::Relaxed);
+        let health = (0..len).map(|i| self.health(i)).collect::<Vec<_>>();
+
+        let mut order = (0..len).map(|i| (active + i) % len).collect::<Vec<_>>();
+        if self.prefer_highest_height {
+            order.sort_by_key(|&i| std::cmp::Reverse(health[i].height));
+        }
+        order.sort_by_key(|&i| !health[i].healthy);
+        order
+    }
+
+    fn record_success(&self, index: usize) {
+        let mut state = self.nodes[index].state.lock().unwrap();
+        state.healthy = true;
+        state.consecutive_failures = 0;
+        state.last_error = None;
+    }
+
+    fn record_failure(&self, index: usize, error: String) {
+        warn!("Node {} failed: {}", self.nodes[index].address, error);
+        let mut state = self.nodes[index].state.lock().unwrap();
+        state.healthy = false;
+        state.consecutive_failures += 1;
+        state.last_error = Some(error);
+    }
+
+    /// Store the result of a health check on the node at `index`.
+    pub(crate) fn record_health(&self, index: usize, result: anyhow::Result<(u64, (u16, u16))>) {
+        match result {
+            Ok((height, version)) => {
+                let mut state = self.nodes[index].state.lock().unwrap();
+                state.healthy = true;
+                state.height = Some(height);
+                state.version = Some(version);
+                state.consecutive_failures = 0;
+                state.last_error = None;
+            }
+            Err(e) => self.record_failure(index, e.to_string()),
+        }
+    }
+
+    /// Make the best candidate the active node, e.g. after a health check.
+    pub(crate) fn select_active(&self) {
+        if let Some(&best) = self.candidates().first() {
+            self.active.store(best, Ordering::Relaxed);
+        }
+    }
+
+    /// Run `call` on the nodes in order until one of them answers without a transport error and
+    /// without a `BUSY` status. A non-idempotent `method` fails over only if it was certainly not
+    /// processed, e.g. the connection failed, so that it never runs on two nodes.
+    pub(crate) async fn call<'a, F, Fut, T>(&'a self, method: &str, call: F) -> anyhow::Result<T>
+    where
+        F: Fn(&'a str) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+        T: NodeResponse,
+    {
+        if self.nodes.len() == 1 {
+            return call(&self.nodes[0].address).await;
+        }
+
+        let mut last = None;
+        for index in self.candidates() {
+            match call(&self.nodes[index].address).await {
+                Ok(rsp) if rsp.is_busy() => {
+                    self.record_failure(index, "Node is busy".to_string());
+                    last = Some(Ok(rsp));
+                }
+                Ok(rsp) => {
+                    self.record_success(index);
+                    self.active.store(index, Ordering::Relaxed);
+                    return Ok(rsp);
+                }
+                Err(e) => {
+                    let resend = may_resend(method, ErrorClass::of(&e));
+                    self.record_failure(index, e.to_string());
+                    if !resend {
+                        return Err(e);
+                    }
+                    last = Some(Err(e));
+                }
+            }
+        }
+
+        last.expect("the pool contains at least one node")
+    }
+
+    /// Snapshot of the state of every node.
+    pub(crate) fn states(&self) -> Vec<NodeState> {
+        let active = self.active.load(Ordering::Relaxed);
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let health = node.state.lock().unwrap().clone();
+                NodeState {
+                    address: node.address.clone(),
+                    healthy: health.healthy,
+                    active: i == active,
+                    height: health.height,
+                    version: health.version,
+                    consecutive_failures: health.consecutive_failures,
+                    last_error: health.last_error,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Rsp(bool);
+
+    impl NodeResponse for Rsp {
+        fn is_busy(&self) -> bool {
+            self.0
+        }
+    }
+
+    fn pool(prefer_highest_height: bool) -> NodePool {
+        NodePool::new(
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            prefer_highest_height,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn node_pool_requires_a_node() {
+        assert!(NodePool::new(vec![], false).is_err());
+    }
+
+    #[tokio::test]
+    async fn node_pool_fails_over_on_error_and_busy() {
+        let pool = pool(false);
+
+        let rsp = pool
+            .call("get_block_count", |addr| {
+                let addr = addr.to_string();
+                async move {
+                    match addr.as_str() {
+                        "a" => Err(anyhow::Error::msg("connection refused")),
+                        "b" => Ok(Rsp(true)),
+                        _ => Ok(Rsp(false)),
+                    }
+                }
+            })
+            .await
+            .unwrap();
+        assert!(!rsp.is_busy());
+
+        let states = pool.states();
+        assert!(!states[0].healthy);
+        assert_eq!(states[0].consecutive_failures, 1);
+        assert_eq!(states[0].last_error.as_deref(), Some("connection refused"));
+        assert!(!states[1].healthy);
+        assert_eq!(states[1].last_error.as_deref(), Some("Node is busy"));
+        assert!(states[2].healthy);
+        assert!(states[2].active);
+
+        // healthy nodes are tried first, unhealthy ones last
+        assert_eq!(pool.candidates(), vec![2, 0, 1]);
+    }
+
+    #[tokio::test]
+    async fn node_pool_returns_last_error_when_all_nodes_fail() {
+        let pool = pool(false);
+
+        let err = pool
+            .call("get_block_count", |addr| {
+                let addr = addr.to_string();
+                async move { Err::<Rsp, _>(anyhow::Error::msg(format!("{} is down", addr))) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "c is down");
+        assert!(pool.states().iter().all(|state| !state.healthy));
+    }
+
+    #[tokio::test]
+    async fn node_pool_does_not_resend_non_idempotent_calls() {
+        let pool = pool(false);
+        let connect_error = reqwest::get("http://127.0.0.1:1").await.unwrap_err();
+        let connect_error = connect_error.to_string();
+
+        // the node may have processed the call before failing
+        let err = pool
+            .call("submit_block", |addr| {
+                let addr = addr.to_string();
+                async move { Err::<Rsp, _>(anyhow::Error::msg(format!("{} timed out", addr))) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "a timed out");
+        let states = pool.states();
+        assert!(!states[0].healthy);
+        assert!(states[1].healthy && states[2].healthy);
+
+        // the connection failed, so the call was not sent
+        let rsp = pool
+            .call("submit_block", |addr| {
+                let addr = addr.to_string();
+                async move {
+                    match addr.as_str() {
+                        "b" => Err(reqwest::get("http://127.0.0.1:1").await.unwrap_err().into()),
+                        _ => Ok(Rsp(false)),
+                    }
+                }
+            })
+            .await;
+        assert!(rsp.is_ok());
+        let states = pool.states();
+        assert_eq!(
+            states[1].last_error.as_deref(),
+            Some(connect_error.as_str())
+        );
+        assert!(states[2].active);
+    }
+
+    #[test]
+    fn node_pool_prefers_highest_height() {
+        let pool = pool(true);
+        pool.record_health(0, Ok((10, (3, 14))));
+        pool.record_health(1, Ok((12, (3, 14))));
+        pool.record_health(2, Err(anyhow::Error::msg("timeout")));
+        pool.select_active();
+
+        assert_eq!(pool.candidates(), vec![1, 0, 2]);
+
+        let states = pool.states();
+        assert!(states[1].active);
+        assert_eq!(states[1].height, Some(12));
+        assert_eq!(states[1].version, Some((3, 14)));
+        assert!(!states[2].healthy);
+    }
+}
@@ -48,18 +48,66 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## Tracing and metrics
+//!
+//! Each call is wrapped in a `rpc_call` [`tracing`] span at debug level, with the fields
+//! `method`, `request_id`, `endpoint` (the node that answered last), `duration_ms`, `status` and
+//! `error_class`. Requests and responses are logged at trace level, with wallet passwords,
+//! private keys, seeds and transaction keys masked.
+//!
+//! With the `metrics` feature, the following metrics are also exported through the
+//! [`metrics`](https://docs.rs/metrics) facade, labelled by `method`:
+//!
+//! - `monero_rpc_requests_total` counter, also labelled by `status`,
+//! - `monero_rpc_request_duration_seconds` histogram,
+//! - `monero_rpc_errors_total` counter, also labelled by [`ErrorClass`].
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 // Coding conventions
 #![forbid(unsafe_code)]
 
+pub use jsonrpc_core;
 pub use monero;
 
 #[macro_use]
 mod util;
+mod batch;
+mod cache;
+mod epee;
+mod failover;
+mod limit;
+#[cfg(any(test, feature = "mock"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "mock")))]
+pub mod mock;
 mod models;
-
-pub use self::{models::*, util::*};
+mod redact;
+mod replay;
+mod restricted;
+mod retry;
+mod secret;
+mod telemetry;
+mod transport;
+
+pub use self::{
+    batch::{BatchEntry, BatchResults, DaemonJsonRpcBatch, WalletBatch},
+    cache::{CacheKey, LruCache, ResponseCache},
+    failover::NodeState,
+    limit::RateLimit,
+    models::*,
+    replay::{RecordingTransport, ReplayTransport},
+    restricted::{DaemonCapabilities, RestrictedMethodError},
+    retry::{ErrorClass, RetryPolicy},
+    secret::{Secret, Wipe},
+    transport::{BatchNotSupported, HttpTransport, Transport, TransportFuture},
+    util::*,
+};
+use self::{
+    cache::CacheLayer,
+    failover::{NodePool, NodeResponse},
+    limit::{Limiter, Limits},
+    telemetry::CallStatus,
+};
 
 use jsonrpc_core::types::{Id, *};
 use monero::{
@@ -78,15 +126,13 @@ use std::{
     iter::{empty, once},
     num::NonZeroU64,
     ops::{Deref, RangeInclusive},
+    path::PathBuf,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tracing::*;
 use uuid::Uuid;
 
-#[cfg(feature = "rpc_authentication")]
-use diqwest::WithDigestAuth;
-
 enum RpcParams {
     Array(Box<dyn Iterator<Item = Value> + Send + 'static>),
     Map(Box<dyn Iterator<Item = (String, Value)> + Send + 'static>),
@@ -130,89 +176,441 @@ impl From<RpcParams> for Params {
     }
 }
 
-#[derive(Clone, Debug)]
+impl NodeResponse for response::Output {
+    fn is_busy(&self) -> bool {
+        match self {
+            response::Output::Success(s) => s.result.is_busy(),
+            response::Output::Failure(_) => false,
+        }
+    }
+}
+
+impl NodeResponse for Vec<response::Output> {
+    fn is_busy(&self) -> bool {
+        self.iter().any(NodeResponse::is_busy)
+    }
+}
+
+impl NodeResponse for Value {
+    fn is_busy(&self) -> bool {
+        self.get("status").and_then(Value::as_str) == Some("BUSY")
+    }
+}
+
+impl NodeResponse for Vec<u8> {
+    /// Binary responses are only decoded by the caller.
+    fn is_busy(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug)]
 struct RemoteCaller {
-    http_client: reqwest::Client,
-    addr: String,
-    #[cfg(feature = "rpc_authentication")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "rpc_authentication")))]
-    rpc_auth: RpcAuthentication,
+    transport: Arc<dyn Transport>,
+    nodes: NodePool,
+    retry_policy: Option<RetryPolicy>,
+    limiter: Limiter,
+    cache: Option<CacheLayer>,
 }
 
 impl RemoteCaller {
+    async fn send_json_rpc(
+        &self,
+        span: &Span,
+        addr: &str,
+        method_call: &MethodCall,
+    ) -> anyhow::Result<response::Output> {
+        let _guard = self.limiter.acquire(&method_call.method).await;
+        telemetry::record_endpoint(span, addr);
+        trace!(
+            "Sending JSON-RPC method call: {}",
+            redact::method_call(method_call)
+        );
+
+        let rsp = self.transport.json_rpc(addr, method_call).await?;
+
+        trace!(
+            "Received JSON-RPC response: {}",
+            redact::output(&method_call.method, &rsp)
+        );
+        Ok(rsp)
+    }
+
     async fn json_rpc_call(
         &self,
         method: &'static str,
         params: RpcParams,
     ) -> anyhow::Result<jsonrpc_core::Result<Value>> {
-        let client = self.http_client.clone();
-        let uri = format!("{}/json_rpc", &self.addr);
-
+        let request_id = Uuid::new_v4().to_string();
+        let span = telemetry::call_span(method, Some(&request_id));
         let method_call = MethodCall {
             jsonrpc: Some(Version::V2),
             method: method.to_string(),
             params: params.into(),
-            id: Id::Str(Uuid::new_v4().to_string()),
+            id: Id::Str(request_id),
         };
 
-        trace!("Sending JSON-RPC method call: {:?}", method_call);
+        let start = Instant::now();
+
+        let (result, status) = async {
+            if let Some(cached) = self
+                .cache
+                .as_ref()
+                .and_then(|cache| cache.get(method, &method_call.params))
+            {
+                return (Ok(Ok(cached)), CallStatus::Cached);
+            }
+
+            let result = async {
+                let rsp = self
+                    .with_retry(method, || {
+                        self.nodes
+                            .call(method, |addr| self.send_json_rpc(&span, addr, &method_call))
+                    })
+                    .await?;
+
+                let v = jsonrpc_core::Result::<Value>::from(rsp);
+                if let (Some(cache), Ok(value)) = (&self.cache, &v) {
+                    cache.store(method, &method_call.params, value);
+                }
+                Ok(v)
+            }
+            .await;
+
+            let status = CallStatus::of_json_rpc(&result);
+            (result, status)
+        }
+        .instrument(span.clone())
+        .await;
 
-        let req = client.post(&uri).json(&method_call);
+        telemetry::record_call(&span, method, start, status);
+        result
+    }
 
-        #[cfg(not(feature = "rpc_authentication"))]
-        let rsp = req.send().await?.json::<response::Output>().await?;
+    /// Send a batch in a single request, or else each of its calls under the limits of its own
+    /// method when the node does not support batches.
+    async fn send_json_rpc_batch(
+        &self,
+        span: &Span,
+        addr: &str,
+        method_calls: &[MethodCall],
+    ) -> anyhow::Result<Vec<response::Output>> {
+        let rsp = {
+            let _guard = self.limiter.acquire("batch").await;
+            telemetry::record_endpoint(span, addr);
+            trace!(
+                "Sending JSON-RPC batch: {}",
+                serde_json::Value::Array(method_calls.iter().map(redact::method_call).collect())
+            );
 
-        #[cfg(feature = "rpc_authentication")]
-        let rsp = if let RpcAuthentication::Credentials { username, password } = &self.rpc_auth {
-            req.send_with_digest_auth(username, password)
-                .await?
-                .json::<response::Output>()
-                .await?
-        } else {
-            req.send().await?.json::<response::Output>().await?
+            self.transport.json_rpc_batch(addr, method_calls).await
         };
 
-        trace!("Received JSON-RPC response: {:?}", rsp);
-        let v = jsonrpc_core::Result::<Value>::from(rsp);
-        Ok(v)
+        let rsp = match rsp {
+            Err(e) if e.is::<BatchNotSupported>() => {
+                debug!("Node does not support batches, sending the calls one by one");
+                let mut outputs = Vec::with_capacity(method_calls.len());
+                for method_call in method_calls {
+                    outputs.push(self.send_json_rpc(span, addr, method_call).await?);
+                }
+                return Ok(outputs);
+            }
+            rsp => rsp?,
+        };
+
+        trace!(
+            "Received JSON-RPC batch response: {}",
+            serde_json::Value::Array(
+                rsp.iter()
+                    .map(|output| {
+                        let method = method_calls
+                            .iter()
+                            .find(|method_call| &method_call.id == output.id())
+                            .map_or("", |method_call| method_call.method.as_str());
+                        redact::output(method, output)
+                    })
+                    .collect()
+            )
+        );
+        Ok(rsp)
     }
 
-    async fn daemon_rpc_call<T>(&self, method: &'static str, params: RpcParams) -> anyhow::Result<T>
-    where
-        T: for<'de> Deserialize<'de> + Send + 'static + Debug,
-    {
-        let client = self.http_client.clone();
-        let uri = format!("{}/{}", &self.addr, method);
+    /// Send several method calls at once and return their results in the same order.
+    async fn json_rpc_batch_call(
+        &self,
+        calls: Vec<(&'static str, Params)>,
+    ) -> anyhow::Result<Vec<jsonrpc_core::Result<Value>>> {
+        let method_calls = calls
+            .into_iter()
+            .map(|(method, params)| MethodCall {
+                jsonrpc: Some(Version::V2),
+                method: method.to_string(),
+                params,
+                id: Id::Str(Uuid::new_v4().to_string()),
+            })
+            .collect::<Vec<_>>();
+
+        let span = telemetry::call_span("batch", None);
+        let start = Instant::now();
+        let result = self
+            .with_retry("batch", || {
+                self.nodes.call("batch", |addr| {
+                    self.send_json_rpc_batch(&span, addr, &method_calls)
+                })
+            })
+            .instrument(span.clone())
+            .await;
+        let status = match &result {
+            Ok(outputs) if outputs.is_busy() => CallStatus::Busy,
+            Ok(_) => CallStatus::Ok,
+            Err(e) => CallStatus::Error(ErrorClass::of(e)),
+        };
+        telemetry::record_call(&span, "batch", start, status);
+        let mut outputs = result?;
+
+        Ok(method_calls
+            .iter()
+            .map(|method_call| {
+                match outputs
+                    .iter()
+                    .position(|output| output.id() == &method_call.id)
+                {
+                    Some(index) => jsonrpc_core::Result::<Value>::from(outputs.swap_remove(index)),
+                    None => Err(jsonrpc_core::Error {
+                        code: ErrorCode::InternalError,
+                        message: format!("No response to {} in batch", method_call.method),
+                        data: None,
+                    }),
+                }
+            })
+            .collect())
+    }
 
+    async fn send_daemon_rpc(
+        &self,
+        span: &Span,
+        addr: &str,
+        method: &'static str,
+        json_params: &Params,
+    ) -> anyhow::Result<Value> {
+        let _guard = self.limiter.acquire(method).await;
+        telemetry::record_endpoint(span, addr);
+        trace!(
+            "Sending daemon RPC call: {:?}, with params {}",
+            method,
+            redact::params(json_params)
+        );
+
+        let rsp = self.transport.other_rpc(addr, method, json_params).await?;
+
+        trace!(
+            "Received daemon RPC response: {}",
+            redact::response(method, &rsp)
+        );
+
+        Ok(rsp)
+    }
+
+    async fn daemon_rpc_call(
+        &self,
+        method: &'static str,
+        params: RpcParams,
+    ) -> anyhow::Result<Value> {
         let json_params: Params = params.into();
+        let span = telemetry::call_span(method, None);
+        let start = Instant::now();
+
+        let (result, status) = async {
+            if let Some(cached) = self
+                .cache
+                .as_ref()
+                .and_then(|cache| cache.get(method, &json_params))
+            {
+                return (Ok(cached), CallStatus::Cached);
+            }
+
+            let result = self
+                .with_retry(method, || {
+                    self.nodes.call(method, |addr| {
+                        self.send_daemon_rpc(&span, addr, method, &json_params)
+                    })
+                })
+                .await;
+
+            if let (Some(cache), Ok(rsp)) = (&self.cache, &result) {
+                cache.store(method, &json_params, rsp);
+            }
+            let status = CallStatus::of_daemon_rpc(&result);
+            (result, status)
+        }
+        .instrument(span.clone())
+        .await;
+
+        telemetry::record_call(&span, method, start, status);
+        result
+    }
 
+    async fn send_binary_rpc(
+        &self,
+        span: &Span,
+        addr: &str,
+        method: &'static str,
+        body: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        let _guard = self.limiter.acquire(method).await;
+        telemetry::record_endpoint(span, addr);
         trace!(
-            "Sending daemon RPC call: {:?}, with params {:?}",
+            "Sending daemon binary RPC call: {:?}, with {} bytes",
             method,
-            json_params
+            body.len()
         );
 
-        let req = client.post(uri).json(&json_params);
+        let rsp = self
+            .transport
+            .binary_rpc(addr, method, body.to_vec())
+            .await?;
 
-        #[cfg(not(feature = "rpc_authentication"))]
-        let rsp = req.send().await?.json::<T>().await?;
+        trace!("Received daemon binary RPC response of {} bytes", rsp.len());
+        Ok(rsp)
+    }
 
-        #[cfg(feature = "rpc_authentication")]
-        let rsp = if let RpcAuthentication::Credentials { username, password } = &self.rpc_auth {
-            req.send_with_digest_auth(username, password)
-                .await?
-                .json::<T>()
-                .await?
-        } else {
-            req.send().await?.json::<T>().await?
-        };
+    async fn binary_rpc_call(
+        &self,
+        method: &'static str,
+        body: Vec<u8>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let span = telemetry::call_span(method, None);
+        let start = Instant::now();
 
-        trace!("Received daemon RPC response: {:?}", rsp);
+        let result = self
+            .with_retry(method, || {
+                self.nodes.call(method, |addr| {
+                    self.send_binary_rpc(&span, addr, method, &body)
+                })
+            })
+            .instrument(span.clone())
+            .await;
 
-        Ok(rsp)
+        telemetry::record_call(&span, method, start, CallStatus::of_binary_rpc(&result));
+        result
+    }
+
+    async fn with_retry<F, Fut, T>(&self, method: &'static str, call: F) -> anyhow::Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<T>>,
+        T: NodeResponse,
+    {
+        match &self.retry_policy {
+            Some(policy) => policy.run(method, call).await,
+            None => call().await,
+        }
+    }
+
+    /// Query height and version of every node, bypassing failover, and select the best node.
+    async fn health_check(&self) -> Vec<NodeState> {
+        #[derive(Deserialize)]
+        struct Count {
+            count: u64,
+        }
+
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        for (index, addr) in self.nodes.addresses().enumerate() {
+            // each request gets its own span, not the one of the caller
+            let send = |method: &'static str| {
+                let request_id = Uuid::new_v4().to_string();
+                let span = telemetry::call_span(method, Some(&request_id));
+                let method_call = MethodCall {
+                    jsonrpc: Some(jsonrpc_core::Version::V2),
+                    method: method.to_string(),
+                    params: Params::None,
+                    id: Id::Str(request_id),
+                };
+                async move {
+                    self.send_json_rpc(&span, addr, &method_call)
+                        .instrument(span.clone())
+                        .await
+                }
+            };
+
+            let result = async {
+                let rsp = send("get_block_count").await?;
+                if rsp.is_busy() {
+                    return Err(anyhow::Error::msg("Node is busy"));
+                }
+                let height = serde_json::from_value::<MoneroResult<Count>>(
+                    jsonrpc_core::Result::<Value>::from(rsp)?,
+                )?
+                .into_inner()
+                .count;
+
+                let rsp = send("get_version").await?;
+                let version =
+                    serde_json::from_value::<Version>(jsonrpc_core::Result::<Value>::from(rsp)?)?
+                        .version;
+
+                Ok((height, split_version(version)?))
+            }
+            .await;
+
+            self.nodes.record_health(index, result);
+        }
+
+        self.nodes.select_active();
+        self.nodes.states()
+    }
+}
+
+impl GetBlockHeaderSelector {
+    /// Method and parameters of the request for the selected block header.
+    fn into_request(self) -> (&'static str, RpcParams) {
+        match self {
+            GetBlockHeaderSelector::Last => ("get_last_block_header", RpcParams::None),
+            GetBlockHeaderSelector::Hash(hash) => (
+                "get_block_header_by_hash",
+                RpcParams::map(
+                    Some(("hash", serde_json::to_value(HashString(hash)).unwrap())).into_iter(),
+                ),
+            ),
+            GetBlockHeaderSelector::Height(height) => (
+                "get_block_header_by_height",
+                RpcParams::map(Some(("height", height.into())).into_iter()),
+            ),
+        }
     }
 }
 
+/// Parameters of the `get_balance` and `get_address` wallet methods.
+fn account_params(
+    account_index: u32,
+    address_indices_key: &'static str,
+    address_indices: Option<Vec<u32>>,
+) -> RpcParams {
+    RpcParams::map(
+        empty()
+            .chain(once(("account_index", account_index.into())))
+            .chain(address_indices.map(|v| {
+                (
+                    address_indices_key,
+                    v.into_iter().map(Value::from).collect::<Vec<_>>().into(),
+                )
+            })),
+    )
+}
+
+/// Split an RPC version in its Major & Minor parts, where Major is the first 16 bits and Minor
+/// the last 16 bits.
+fn split_version(version: u32) -> anyhow::Result<(u16, u16)> {
+    let major = version >> 16;
+    let minor = version - (major << 16);
+
+    Ok((u16::try_from(major)?, u16::try_from(minor)?))
+}
+
 #[derive(Clone, Debug)]
 struct CallerWrapper(Arc<RemoteCaller>);
 
@@ -221,8 +619,12 @@ impl CallerWrapper {
     where
         T: for<'de> Deserialize<'de> + Send + 'static,
     {
-        let c = self.0.json_rpc_call(method, params);
-        Ok(serde_json::from_value(c.await??)?)
+        let value = self
+            .0
+            .json_rpc_call(method, params)
+            .await?
+            .map_err(|e| restricted::json_rpc_error(method, e))?;
+        Ok(serde_json::from_value(value)?)
     }
 
     async fn daemon_rpc_request<T>(
@@ -233,9 +635,38 @@ impl CallerWrapper {
     where
         T: for<'de> Deserialize<'de> + Send + 'static + Debug,
     {
-        let c = self.0.daemon_rpc_call(method, params).await?;
+        let c = self
+            .0
+            .daemon_rpc_call(method, params)
+            .await
+            .map_err(|e| restricted::daemon_rpc_error(method, e))?;
         Ok(serde_json::from_value(c)?)
     }
+
+    async fn daemon_binary_request(
+        &self,
+        method: &'static str,
+        request: epee::Section,
+    ) -> anyhow::Result<epee::Section> {
+        let rsp = self
+            .0
+            .binary_rpc_call(method, epee::to_bytes(&request)?)
+            .await
+            .map_err(|e| restricted::daemon_rpc_error(method, e))?;
+        let rsp = epee::from_bytes(&rsp)?;
+
+        match rsp.blob("status")? {
+            b"OK" => Ok(rsp),
+            status => Err(anyhow::Error::msg(format!(
+                "Daemon answered with status {}",
+                String::from_utf8_lossy(status)
+            ))),
+        }
+    }
+
+    fn nodes(&self) -> Vec<NodeState> {
+        self.0.nodes.states()
+    }
 }
 
 /// Base RPC client. It is useless on its own, please see the attached methods to see how to
@@ -252,6 +683,13 @@ struct RpcClientConfig {
     rpc_auth: RpcAuthentication,
     proxy_address: Option<String>,
     timeout: Option<Duration>,
+    prefer_highest_height: bool,
+    retry_policy: Option<RetryPolicy>,
+    transport: Option<Arc<dyn Transport>>,
+    record_path: Option<PathBuf>,
+    limits: Limits,
+    cache: Option<Arc<dyn ResponseCache>>,
+    cache_min_depth: u64,
 }
 
 /// Builder for generating a configured [`RpcClient`].
@@ -275,6 +713,13 @@ impl RpcClientBuilder {
                 rpc_auth: RpcAuthentication::None,
                 proxy_address: None,
                 timeout: None,
+                prefer_highest_height: false,
+                retry_policy: None,
+                transport: None,
+                record_path: None,
+                limits: Limits::default(),
+                cache: None,
+                cache_min_depth: 10,
             },
         }
     }
@@ -299,23 +744,186 @@ impl RpcClientBuilder {
         self
     }
 
+    /// Retry failed calls according to the given policy. By default, every call is attempted
+    /// only once.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry_policy = Some(policy);
+        self
+    }
+
+    /// Limit the number of requests in flight at the same time, shared by all the clones of the
+    /// client. Requests over the limit wait for a slot.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.config.limits.max_in_flight = Some(max);
+        self
+    }
+
+    /// Limit the rate of requests, shared by all the clones of the client. Requests over the
+    /// limit wait for their turn.
+    ///
+    /// ```rust
+    /// # fn main() -> anyhow::Result<()> {
+    /// use monero_rpc::{RateLimit, RpcClientBuilder};
+    ///
+    /// let client = RpcClientBuilder::new()
+    ///     .max_in_flight(8)
+    ///     .rate_limit(RateLimit::per_second(20).burst(40))
+    ///     .method_rate_limit("get_block", RateLimit::per_second(5))
+    ///     .build("http://node.monerooutreach.org:18081")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.config.limits.rate_limit = Some(limit);
+        self
+    }
+
+    /// Limit the number of requests to `method` in flight at the same time, on top of the limit
+    /// set with [`Self::max_in_flight`]. A JSON-RPC batch counts as one request to `batch`, or
+    /// as one request per call when the node does not support batches.
+    pub fn method_max_in_flight(mut self, method: impl Into<String>, max: usize) -> Self {
+        self.config
+            .limits
+            .methods
+            .entry(method.into())
+            .or_default()
+            .0 = Some(max);
+        self
+    }
+
+    /// Limit the rate of requests to `method`, on top of the limit set with
+    /// [`Self::rate_limit`]. A JSON-RPC batch counts as one request to `batch`, or as one request
+    /// per call when the node does not support batches.
+    pub fn method_rate_limit(mut self, method: impl Into<String>, limit: RateLimit) -> Self {
+        self.config
+            .limits
+            .methods
+            .entry(method.into())
+            .or_default()
+            .1 = Some(limit);
+        self
+    }
+
+    /// Cache the daemon responses that can no longer change in the given store.
+    ///
+    /// Only `on_get_block_hash`, block headers, blocks and confirmed transactions at least
+    /// [`Self::cache_min_depth`] blocks below the tip are cached. Calls depending on the tip, such
+    /// as `get_block_count` or `get_last_block_header`, always go to the node, and so do batches.
+    ///
+    /// ```rust
+    /// # fn main() -> anyhow::Result<()> {
+    /// use monero_rpc::{LruCache, RpcClientBuilder};
+    ///
+    /// let client = RpcClientBuilder::new()
+    ///     .cache(LruCache::new(10_000))
+    ///     .build("http://node.monerooutreach.org:18081")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cache(mut self, cache: impl ResponseCache + 'static) -> Self {
+        self.config.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Minimum depth below the tip for a response to be cached, 10 blocks by default. Blocks
+    /// that deep are not expected to be reorganized.
+    pub fn cache_min_depth(mut self, depth: u64) -> Self {
+        self.config.cache_min_depth = depth;
+        self
+    }
+
+    /// Send requests with the given transport instead of the default [`HttpTransport`].
+    ///
+    /// The proxy, timeout and authentication configured on this builder only apply to the
+    /// default transport and are ignored when a custom one is given.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.config.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Record every request/response pair to a fixture file at `path`, to be served back later
+    /// by a [`ReplayTransport`], with secrets masked. See [`RecordingTransport`].
+    pub fn record_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.record_path = Some(path.into());
+        self
+    }
+
+    /// When failing over between several nodes, prefer the healthy node with the highest height
+    /// known from the last [`RpcClient::health_check`]. See [`Self::build_with_failover`].
+    pub fn prefer_highest_height(mut self, prefer: bool) -> Self {
+        self.config.prefer_highest_height = prefer;
+        self
+    }
+
     /// Build and return the fully configured RPC client.
     pub fn build(self, addr: impl Into<String>) -> anyhow::Result<RpcClient> {
+        self.build_with_failover(once(addr))
+    }
+
+    /// Build and return the fully configured RPC client, spreading calls over several daemon
+    /// addresses.
+    ///
+    /// Calls are sent to the active node. On a transport error or a `BUSY` status, the node is
+    /// marked unhealthy and the call is retried on the next node, healthy nodes first. Calls
+    /// changing state, such as `submit_block` or `relay_tx`, are only retried if the node could
+    /// not be reached or was busy, so that they never run on two nodes. Error responses from the
+    /// daemon itself are returned as is. Failover is meant for daemons: a
+    /// wallet RPC holds state and should be built with [`Self::build`].
+    ///
+    /// ```rust
+    /// # fn main() -> anyhow::Result<()> {
+    /// use monero_rpc::RpcClientBuilder;
+    ///
+    /// let client = RpcClientBuilder::new()
+    ///     .prefer_highest_height(true)
+    ///     .build_with_failover([
+    ///         "http://node.monerooutreach.org:18081",
+    ///         "http://node.community.rino.io:18081",
+    ///     ])?;
+    /// let daemon = client.daemon();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn build_with_failover<I>(self, addrs: I) -> anyhow::Result<RpcClient>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
         let config = self.config;
-        let mut http_client_builder = reqwest::ClientBuilder::new();
-        if let Some(proxy_address) = config.proxy_address {
-            http_client_builder = http_client_builder.proxy(reqwest::Proxy::all(proxy_address)?);
+        let nodes = NodePool::new(
+            addrs.into_iter().map(Into::into).collect(),
+            config.prefer_highest_height,
+        )?;
+        let transport = match config.transport {
+            Some(transport) => transport,
+            None => {
+                let mut http_client_builder = reqwest::ClientBuilder::new();
+                if let Some(proxy_address) = config.proxy_address {
+                    http_client_builder =
+                        http_client_builder.proxy(reqwest::Proxy::all(proxy_address)?);
+                };
+                if let Some(timeout) = config.timeout {
+                    http_client_builder = http_client_builder.timeout(timeout);
+                };
+                let transport = HttpTransport::new(http_client_builder.build()?);
+                #[cfg(feature = "rpc_authentication")]
+                let transport = transport.rpc_authentication(config.rpc_auth);
+                Arc::new(transport)
+            }
         };
-        if let Some(timeout) = config.timeout {
-            http_client_builder = http_client_builder.timeout(timeout);
+        let transport: Arc<dyn Transport> = match config.record_path {
+            Some(path) => Arc::new(RecordingTransport::new(transport, path)?),
+            None => transport,
         };
-        let http_client = http_client_builder.build()?;
         Ok(RpcClient {
             inner: CallerWrapper(Arc::new(RemoteCaller {
-                http_client,
-                addr: addr.into(),
-                #[cfg(feature = "rpc_authentication")]
-                rpc_auth: config.rpc_auth,
+                transport,
+                nodes,
+                retry_policy: config.retry_policy,
+                limiter: Limiter::new(config.limits),
+                cache: config
+                    .cache
+                    .map(|cache| CacheLayer::new(cache, config.cache_min_depth)),
             })),
         })
     }
@@ -330,6 +938,17 @@ impl RpcClient {
         RpcClientBuilder::new().build(addr)
     }
 
+    /// Snapshot of the state of the nodes behind this client.
+    pub fn nodes(&self) -> Vec<NodeState> {
+        self.inner.nodes()
+    }
+
+    /// Query `get_block_count` and `get_version` on every daemon node behind this client, update
+    /// their state and select the active node accordingly.
+    pub async fn health_check(&self) -> Vec<NodeState> {
+        self.inner.0.health_check().await
+    }
+
     /// Transform the client into the specialized `DaemonJsonRpcClient` that interacts with JSON RPC
     /// methods on daemon.
     pub fn daemon(self) -> DaemonJsonRpcClient {
@@ -396,6 +1015,11 @@ pub enum GetBlockHeaderSelector {
 }
 
 impl DaemonJsonRpcClient {
+    /// Start a batch of calls to be sent in a single request, see [`DaemonJsonRpcBatch`].
+    pub fn batch(&self) -> DaemonJsonRpcBatch {
+        DaemonJsonRpcBatch::new(self.inner.clone())
+    }
+
     /// Look up how many blocks are in the longest chain known to the node.
     pub async fn get_block_count(&self) -> anyhow::Result<NonZeroU64> {
         #[derive(Deserialize)]
@@ -465,19 +1089,93 @@ impl DaemonJsonRpcClient {
         wallet_address: Address,
         reserve_size: u64,
     ) -> anyhow::Result<BlockTemplate> {
+        self.get_block_template_with_args(GetBlockTemplateArgs {
+            wallet_address,
+            reserve_size: Some(reserve_size),
+            extra_nonce: None,
+            prev_block: None,
+        })
+        .await
+    }
+
+    /// Get a block template on which mining a new block, with an extra nonce of our own or on
+    /// top of another block than the tip of the chain.
+    pub async fn get_block_template_with_args(
+        &self,
+        args: GetBlockTemplateArgs,
+    ) -> anyhow::Result<BlockTemplate> {
+        let params = empty()
+            .chain(once((
+                "wallet_address",
+                serde_json::to_value(args.wallet_address)?,
+            )))
+            .chain(args.reserve_size.map(|v| ("reserve_size", v.into())))
+            .chain(
+                args.extra_nonce
+                    .map(|v| ("extra_nonce", hex::encode(v).into())),
+            )
+            .chain(
+                args.prev_block
+                    .map(|v| ("prev_block", HashString(v).to_string().into())),
+            );
+
         Ok(self
             .inner
-            .request::<MoneroResult<BlockTemplate>>(
-                "get_block_template",
-                RpcParams::map(
-                    empty()
-                        .chain(once((
-                            "wallet_address",
-                            serde_json::to_value(wallet_address).unwrap(),
-                        )))
-                        .chain(once(("reserve_size", reserve_size.into()))),
-                ),
-            )
+            .request::<MoneroResult<BlockTemplate>>("get_block_template", RpcParams::map(params))
+            .await?
+            .into_inner())
+    }
+
+    /// Retrieve the data needed to build a block template without the node: the chain tip,
+    /// the difficulty and the transactions waiting in the pool.
+    pub async fn get_miner_data(&self) -> anyhow::Result<MinerData> {
+        Ok(self
+            .inner
+            .request::<MoneroResult<MinerData>>("get_miner_data", RpcParams::None)
+            .await?
+            .into_inner())
+    }
+
+    /// Compute the proof-of-work hash of a block hashing blob, e.g. to verify a share.
+    pub async fn calc_pow(
+        &self,
+        major_version: u8,
+        height: u64,
+        block_blob: Vec<u8>,
+        seed_hash: CryptoNoteHash,
+    ) -> anyhow::Result<CryptoNoteHash> {
+        let params = empty()
+            .chain(once(("major_version", major_version.into())))
+            .chain(once(("height", height.into())))
+            .chain(once(("block_blob", hex::encode(block_blob).into())))
+            .chain(once((
+                "seed_hash",
+                HashString(seed_hash).to_string().into(),
+            )));
+
+        Ok(self
+            .inner
+            .request::<HashString<CryptoNoteHash>>("calc_pow", RpcParams::map(params))
+            .await?
+            .0)
+    }
+
+    /// Commit a block template to the blocks of merge mined chains.
+    pub async fn add_aux_pow(
+        &self,
+        blocktemplate_blob: Vec<u8>,
+        aux_pow: Vec<AuxPow>,
+    ) -> anyhow::Result<AuxPowTemplate> {
+        let params = empty()
+            .chain(once((
+                "blocktemplate_blob",
+                hex::encode(blocktemplate_blob).into(),
+            )))
+            .chain(once(("aux_pow", serde_json::to_value(aux_pow)?)));
+
+        Ok(self
+            .inner
+            .request::<MoneroResult<AuxPowTemplate>>("add_aux_pow", RpcParams::map(params))
             .await?
             .into_inner())
     }
@@ -504,19 +1202,7 @@ impl DaemonJsonRpcClient {
             block_header: BlockHeaderResponseR,
         }
 
-        let (request, params) = match selector {
-            GetBlockHeaderSelector::Last => ("get_last_block_header", RpcParams::None),
-            GetBlockHeaderSelector::Hash(hash) => (
-                "get_block_header_by_hash",
-                RpcParams::map(
-                    Some(("hash", serde_json::to_value(HashString(hash)).unwrap())).into_iter(),
-                ),
-            ),
-            GetBlockHeaderSelector::Height(height) => (
-                "get_block_header_by_height",
-                RpcParams::map(Some(("height", height.into())).into_iter()),
-            ),
-        };
+        let (request, params) = selector.into_request();
 
         Ok(self
             .inner
@@ -563,10 +1249,321 @@ impl DaemonJsonRpcClient {
             .request::<Rsp>("get_version", RpcParams::None)
             .await?;
 
-        let major = version.version >> 16;
-        let minor = version.version - (major << 16);
+        split_version(version.version)
+    }
+
+    /// Look up the state of the hard forks, for the version the node is running or the given
+    /// `version`.
+    pub async fn hard_fork_info(&self, version: Option<u8>) -> anyhow::Result<HardForkInfo> {
+        let params = empty().chain(version.map(|v| ("version", v.into())));
+
+        Ok(self
+            .inner
+            .request::<MoneroResult<HardForkInfo>>("hard_fork_info", RpcParams::map(params))
+            .await?
+            .into_inner())
+    }
+
+    /// Sum the coins emitted and the fees paid over `count` blocks from `height`.
+    pub async fn get_coinbase_tx_sum(
+        &self,
+        height: u64,
+        count: u64,
+    ) -> anyhow::Result<CoinbaseTxSum> {
+        let params = [("height", height.into()), ("count", count.into())];
+
+        Ok(self
+            .inner
+            .request::<MoneroResult<CoinbaseTxSumR>>(
+                "get_coinbase_tx_sum",
+                RpcParams::map(params.into_iter()),
+            )
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    /// Count the outputs of each amount.
+    pub async fn get_output_histogram(
+        &self,
+        args: OutputHistogramArgs,
+    ) -> anyhow::Result<Vec<OutputHistogramEntry>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            histogram: Vec<OutputHistogramEntry>,
+        }
+
+        let amounts = args
+            .amounts
+            .into_iter()
+            .map(Amount::as_pico)
+            .collect::<Vec<_>>();
+        let params = once(("amounts", amounts.into()))
+            .chain(args.min_count.map(|v| ("min_count", v.into())))
+            .chain(args.max_count.map(|v| ("max_count", v.into())))
+            .chain(args.unlocked.map(|v| ("unlocked", v.into())))
+            .chain(args.recent_cutoff.map(|v| ("recent_cutoff", v.into())));
+
+        Ok(self
+            .inner
+            .request::<MoneroResult<Rsp>>("get_output_histogram", RpcParams::map(params))
+            .await?
+            .into_inner()
+            .histogram)
+    }
+
+    /// Retrieve the number of outputs of each amount created per block, as used to pick decoys.
+    ///
+    /// With `binary`, the distributions are fetched from `get_output_distribution.bin` and
+    /// decoded, compressed or not.
+    pub async fn get_output_distribution(
+        &self,
+        args: OutputDistributionArgs,
+    ) -> anyhow::Result<Vec<OutputDistribution>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            distributions: Vec<OutputDistributionR>,
+        }
+
+        if args.binary {
+            return self.get_output_distribution_bin(args).await;
+        }
 
-        Ok((u16::try_from(major)?, u16::try_from(minor)?))
+        let amounts = args
+            .amounts
+            .into_iter()
+            .map(Amount::as_pico)
+            .collect::<Vec<_>>();
+        let params = once(("amounts", amounts.into()))
+            .chain(args.from_height.map(|v| ("from_height", v.into())))
+            .chain(args.to_height.map(|v| ("to_height", v.into())))
+            .chain(once(("cumulative", args.cumulative.into())));
+
+        Ok(self
+            .inner
+            .request::<MoneroResult<Rsp>>("get_output_distribution", RpcParams::map(params))
+            .await?
+            .into_inner()
+            .distributions
+            .into_iter()
+            .map(OutputDistribution::from)
+            .collect())
+    }
+
+    async fn get_output_distribution_bin(
+        &self,
+        args: OutputDistributionArgs,
+    ) -> anyhow::Result<Vec<OutputDistribution>> {
+        use epee::{Section, Value::*};
+
+        let amounts = args
+            .amounts
+            .into_iter()
+            .map(|amount| UInt(amount.as_pico()))
+            .collect();
+        let request = Section(
+            empty()
+                .chain(once(("amounts", Array(amounts))))
+                .chain(args.from_height.map(|v| ("from_height", UInt(v))))
+                .chain(args.to_height.map(|v| ("to_height", UInt(v))))
+                .chain(once(("cumulative", Bool(args.cumulative))))
+                .chain(once(("binary", Bool(true))))
+                .chain(once(("compress", Bool(args.compress))))
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        );
+
+        let rsp = self
+            .inner
+            .daemon_binary_request("get_output_distribution.bin", request)
+            .await?;
+
+        rsp.objects("distributions")?
+            .into_iter()
+            .map(|distribution| {
+                let compressed = distribution.bool("compress")?;
+                let blob = if compressed {
+                    distribution.blob("compressed_data")?
+                } else {
+                    distribution.blob("distribution")?
+                };
+
+                Ok(OutputDistribution {
+                    amount: Amount::from_pico(distribution.u64("amount")?),
+                    base: distribution.u64("base")?,
+                    distribution: decode_distribution_blob(blob, compressed)?,
+                    start_height: distribution.u64("start_height")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Retrieve the connections of the node to its peers.
+    pub async fn get_connections(&self) -> anyhow::Result<Vec<Connection>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            connections: Vec<Connection>,
+        }
+
+        Ok(self
+            .inner
+            .request::<MoneroResult<Rsp>>("get_connections", RpcParams::None)
+            .await?
+            .into_inner()
+            .connections)
+    }
+
+    /// Retrieve the hosts and subnets banned by the node.
+    pub async fn get_bans(&self) -> anyhow::Result<Vec<Ban>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            bans: Vec<Ban>,
+        }
+
+        Ok(self
+            .inner
+            .request::<MoneroResult<Rsp>>("get_bans", RpcParams::None)
+            .await?
+            .into_inner()
+            .bans)
+    }
+
+    /// Ban or unban hosts and subnets.
+    pub async fn set_bans(&self, bans: Vec<SetBanArgs>) -> anyhow::Result<()> {
+        let params = once(("bans", serde_json::to_value(bans)?));
+
+        self.inner
+            .request::<MoneroResult<IgnoredAny>>("set_bans", RpcParams::map(params))
+            .await?;
+        Ok(())
+    }
+
+    /// Look up whether a host is banned, returning the number of seconds left until its ban
+    /// expires if it is.
+    pub async fn banned(&self, address: String) -> anyhow::Result<Option<u32>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            banned: bool,
+            seconds: u32,
+        }
+
+        let rsp = self
+            .inner
+            .request::<MoneroResult<Rsp>>(
+                "banned",
+                RpcParams::map(once(("address", address.into()))),
+            )
+            .await?
+            .into_inner();
+
+        Ok(rsp.banned.then_some(rsp.seconds))
+    }
+
+    /// Retrieve the synchronization status of the node, with its peers and the spans of blocks
+    /// being downloaded.
+    pub async fn sync_info(&self) -> anyhow::Result<SyncInfo> {
+        Ok(self
+            .inner
+            .request::<MoneroResult<SyncInfoR>>("sync_info", RpcParams::None)
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    /// Poll [`Self::sync_info`] every `interval` until the node has caught up with its peers,
+    /// calling `on_progress` with each status, and return the last status.
+    ///
+    /// A node which does not know of any higher block reports a target height of 0 and is
+    /// considered synchronized, see [`SyncInfo::is_synced`].
+    ///
+    /// ```rust,no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use monero_rpc::RpcClientBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let daemon = RpcClientBuilder::new()
+    ///     .build("http://localhost:18081")?
+    ///     .daemon();
+    ///
+    /// let info = daemon
+    ///     .wait_until_synced(Duration::from_secs(10), |info| {
+    ///         println!("Synchronizing: {}/{}", info.height, info.target_height)
+    ///     })
+    ///     .await?;
+    /// println!("Synchronized at height {}", info.height);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_until_synced<F>(
+        &self,
+        interval: Duration,
+        mut on_progress: F,
+    ) -> anyhow::Result<SyncInfo>
+    where
+        F: FnMut(&SyncInfo),
+    {
+        loop {
+            let info = self.sync_info().await?;
+            on_progress(&info);
+            if info.is_synced() {
+                return Ok(info);
+            }
+
+            debug!(
+                "Node at height {} of {}, polling again in {:?}",
+                info.height, info.target_height, interval
+            );
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Detect what the node allows, in particular whether it runs with `--restricted-rpc`.
+    ///
+    /// The `restricted` flag of `get_info` is used, and nodes not reporting it are probed with
+    /// `get_connections`. Methods disabled on restricted nodes fail with a
+    /// [`RestrictedMethodError`].
+    pub async fn capabilities(&self) -> anyhow::Result<DaemonCapabilities> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            restricted: Option<bool>,
+        }
+
+        let rsp = self
+            .inner
+            .request::<MoneroResult<Rsp>>("get_info", RpcParams::None)
+            .await?
+            .into_inner();
+
+        let restricted = match rsp.restricted {
+            Some(restricted) => restricted,
+            None => match self
+                .inner
+                .request::<IgnoredAny>("get_connections", RpcParams::None)
+                .await
+            {
+                Ok(_) => false,
+                Err(e) if e.is::<RestrictedMethodError>() => true,
+                Err(e) => return Err(e),
+            },
+        };
+
+        Ok(DaemonCapabilities { restricted })
+    }
+
+    /// Snapshot of the state of the daemon nodes behind this client, see
+    /// [`RpcClientBuilder::build_with_failover`].
+    pub fn nodes(&self) -> Vec<NodeState> {
+        self.inner.nodes()
+    }
+
+    /// Check the health of every daemon node behind this client, see [`RpcClient::health_check`].
+    pub async fn health_check(&self) -> Vec<NodeState> {
+        self.inner.0.health_check().await
     }
 
     /// Enable additional functions for daemons in regtest mode.
@@ -598,6 +1595,214 @@ pub struct DaemonRpcClient {
 }
 
 impl DaemonRpcClient {
+    /// Snapshot of the state of the daemon nodes behind this client, see
+    /// [`RpcClientBuilder::build_with_failover`].
+    pub fn nodes(&self) -> Vec<NodeState> {
+        self.inner.nodes()
+    }
+
+    /// Check the health of every daemon node behind this client, see [`RpcClient::health_check`].
+    pub async fn health_check(&self) -> Vec<NodeState> {
+        self.inner.0.health_check().await
+    }
+
+    /// Retrieve the peers known to the node. With `public_only`, only the peers advertising
+    /// themselves publicly are returned.
+    pub async fn get_peer_list(&self, public_only: Option<bool>) -> anyhow::Result<PeerList> {
+        let params = empty().chain(public_only.map(|v| ("public_only", v.into())));
+
+        Ok(self
+            .inner
+            .daemon_rpc_request::<MoneroResult<PeerList>>("get_peer_list", RpcParams::map(params))
+            .await?
+            .into_inner())
+    }
+
+    /// Set the maximum number of incoming connections, or only look it up with `None`.
+    pub async fn in_peers(&self, limit: Option<u32>) -> anyhow::Result<u32> {
+        #[derive(Debug, Deserialize)]
+        struct Rsp {
+            in_peers: u32,
+        }
+
+        let params = empty()
+            .chain(once(("set", limit.is_some().into())))
+            .chain(once(("in_peers", limit.unwrap_or_default().into())));
+
+        Ok(self
+            .inner
+            .daemon_rpc_request::<MoneroResult<Rsp>>("in_peers", RpcParams::map(params))
+            .await?
+            .into_inner()
+            .in_peers)
+    }
+
+    /// Set the maximum number of outgoing connections, or only look it up with `None`.
+    pub async fn out_peers(&self, limit: Option<u32>) -> anyhow::Result<u32> {
+        #[derive(Debug, Deserialize)]
+        struct Rsp {
+            out_peers: u32,
+        }
+
+        let params = empty()
+            .chain(once(("set", limit.is_some().into())))
+            .chain(once(("out_peers", limit.unwrap_or_default().into())));
+
+        Ok(self
+            .inner
+            .daemon_rpc_request::<MoneroResult<Rsp>>("out_peers", RpcParams::map(params))
+            .await?
+            .into_inner()
+            .out_peers)
+    }
+
+    /// Retrieve the bandwidth limits of the node.
+    pub async fn get_limit(&self) -> anyhow::Result<BandwidthLimits> {
+        Ok(self
+            .inner
+            .daemon_rpc_request::<MoneroResult<BandwidthLimits>>("get_limit", RpcParams::None)
+            .await?
+            .into_inner())
+    }
+
+    /// Set the bandwidth limits of the node, in kB/s, and return the new limits. A limit of 0
+    /// is left unchanged, and a limit of -1 is reset to its default.
+    pub async fn set_limit(
+        &self,
+        limit_down: i64,
+        limit_up: i64,
+    ) -> anyhow::Result<BandwidthLimits> {
+        let params = empty()
+            .chain(once(("limit_down", limit_down.into())))
+            .chain(once(("limit_up", limit_up.into())));
+
+        Ok(self
+            .inner
+            .daemon_rpc_request::<MoneroResult<BandwidthLimits>>(
+                "set_limit",
+                RpcParams::map(params),
+            )
+            .await?
+            .into_inner())
+    }
+
+    /// Retrieve the network traffic statistics of the node.
+    pub async fn get_net_stats(&self) -> anyhow::Result<NetStats> {
+        Ok(self
+            .inner
+            .daemon_rpc_request::<MoneroResult<NetStats>>("get_net_stats", RpcParams::None)
+            .await?
+            .into_inner())
+    }
+
+    /// Look up outputs by amount and global index, as used to pick decoys. RingCT outputs have
+    /// an amount of zero.
+    pub async fn get_outs(&self, outputs: Vec<(Amount, u64)>) -> anyhow::Result<Vec<OutKey>> {
+        #[derive(Debug, Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            outs: Vec<OutKeyR>,
+        }
+
+        let outputs = outputs
+            .into_iter()
+            .map(|(amount, index)| json!({ "amount": amount.as_pico(), "index": index }))
+            .collect::<Vec<_>>();
+        let params = empty()
+            .chain(once(("outputs", outputs.into())))
+            .chain(once(("get_txid", true.into())));
+
+        Ok(self
+            .inner
+            .daemon_rpc_request::<MoneroResult<Rsp>>("get_outs", RpcParams::map(params))
+            .await?
+            .into_inner()
+            .outs
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Same as [`Self::get_outs`], through the binary endpoint of the node, which is more
+    /// compact when looking up many outputs.
+    pub async fn get_outs_bin(&self, outputs: Vec<(Amount, u64)>) -> anyhow::Result<Vec<OutKey>> {
+        use epee::{Section, Value::*};
+
+        let outputs = outputs
+            .into_iter()
+            .map(|(amount, index)| {
+                Object(Section(vec![
+                    ("amount".to_string(), UInt(amount.as_pico())),
+                    ("index".to_string(), UInt(index)),
+                ]))
+            })
+            .collect();
+        let request = Section(vec![
+            ("outputs".to_string(), Array(outputs)),
+            ("get_txid".to_string(), Bool(true)),
+        ]);
+
+        let rsp = self
+            .inner
+            .daemon_binary_request("get_outs.bin", request)
+            .await?;
+
+        rsp.objects("outs")?
+            .into_iter()
+            .map(|out| {
+                Ok(OutKey {
+                    key: monero::PublicKey::from_slice(out.blob("key")?)?,
+                    mask: monero::util::ringct::Key {
+                        key: out.blob("mask")?.try_into()?,
+                    },
+                    unlocked: out.bool("unlocked")?,
+                    height: out.u64("height")?,
+                    txid: CryptoNoteHash(out.blob("txid")?.try_into()?),
+                })
+            })
+            .collect()
+    }
+
+    /// Start mining to `miner_address` with `threads_count` threads. With
+    /// `do_background_mining`, the node only mines when the machine is idle, and with
+    /// `ignore_battery` also when it runs on battery.
+    pub async fn start_mining(
+        &self,
+        miner_address: Address,
+        threads_count: u32,
+        do_background_mining: bool,
+        ignore_battery: bool,
+    ) -> anyhow::Result<()> {
+        let params = empty()
+            .chain(once(("miner_address", miner_address.to_string().into())))
+            .chain(once(("threads_count", threads_count.into())))
+            .chain(once(("do_background_mining", do_background_mining.into())))
+            .chain(once(("ignore_battery", ignore_battery.into())));
+
+        self.inner
+            .daemon_rpc_request::<MoneroResult<IgnoredAny>>("start_mining", RpcParams::map(params))
+            .await?;
+        Ok(())
+    }
+
+    /// Stop mining.
+    pub async fn stop_mining(&self) -> anyhow::Result<()> {
+        self.inner
+            .daemon_rpc_request::<MoneroResult<IgnoredAny>>("stop_mining", RpcParams::None)
+            .await?;
+        Ok(())
+    }
+
+    /// Retrieve the mining status of the node.
+    pub async fn mining_status(&self) -> anyhow::Result<MiningStatus> {
+        Ok(self
+            .inner
+            .daemon_rpc_request::<MoneroResult<MiningStatusR>>("mining_status", RpcParams::None)
+            .await?
+            .into_inner()
+            .into())
+    }
+
     /// Look up one or more transactions by hash.
     pub async fn get_transactions(
         &self,
@@ -664,6 +1869,19 @@ impl Serialize for TransferType {
     }
 }
 
+impl Serialize for SslSupport {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(match self {
+            SslSupport::Autodetect => "autodetect",
+            SslSupport::Enabled => "enabled",
+            SslSupport::Disabled => "disabled",
+        })
+    }
+}
+
 impl Serialize for TransferPriority {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -699,6 +1917,39 @@ impl<'de> Deserialize<'de> for TransferPriority {
     }
 }
 
+impl Serialize for HardForkState {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u8(match self {
+            HardForkState::LikelyForked => 0,
+            HardForkState::UpdateNeeded => 1,
+            HardForkState::Ready => 2,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for HardForkState {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v = u8::deserialize(deserializer)?;
+        Ok(match v {
+            0 => HardForkState::LikelyForked,
+            1 => HardForkState::UpdateNeeded,
+            2 => HardForkState::Ready,
+            other => {
+                return Err(serde::de::Error::custom(format!(
+                    "Invalid variant {}, expected 0-2",
+                    other
+                )))
+            }
+        })
+    }
+}
+
 /// Result of [`RpcClient::wallet`] to interact with a Monero wallet RPC daemon.
 ///
 /// ```rust
@@ -717,6 +1968,11 @@ pub struct WalletClient {
 }
 
 impl WalletClient {
+    /// Start a batch of calls to be sent in a single request, see [`WalletBatch`].
+    pub fn batch(&self) -> WalletBatch {
+        WalletBatch::new(self.inner.clone())
+    }
+
     /// Generate a new wallet from viewkey, address and optionally a spend key.  Requires the rpc
     /// wallet to run with the `--wallet-dir` argument.
     pub async fn generate_from_keys(
@@ -727,9 +1983,18 @@ impl WalletClient {
             .chain(args.restore_height.map(|v| ("restore_height", v.into())))
             .chain(once(("filename", args.filename.into())))
             .chain(once(("address", args.address.to_string().into())))
-            .chain(args.spendkey.map(|v| ("spendkey", v.to_string().into())))
-            .chain(once(("viewkey", args.viewkey.to_string().into())))
-            .chain(once(("password", args.password.into())))
+            .chain(
+                args.spendkey
+                    .map(|v| ("spendkey", v.expose_secret().to_string().into())),
+            )
+            .chain(once((
+                "viewkey",
+                args.viewkey.expose_secret().to_string().into(),
+            )))
+            .chain(once((
+                "password",
+                args.password.expose_secret().as_str().into(),
+            )))
             .chain(
                 args.autosave_current
                     .map(|v| ("autosave_current", v.into())),
@@ -748,12 +2013,15 @@ impl WalletClient {
         let params = empty()
             .chain(args.restore_height.map(|v| ("restore_height", v.into())))
             .chain(once(("filename", args.filename.into())))
-            .chain(once(("seed", args.seed.into())))
+            .chain(once(("seed", args.seed.expose_secret().as_str().into())))
             .chain(
                 args.seed_offset
-                    .map(|v| ("seed_offset", v.to_string().into())),
+                    .map(|v| ("seed_offset", v.expose_secret().as_str().into())),
             )
-            .chain(once(("password", args.password.into())))
+            .chain(once((
+                "password",
+                args.password.expose_secret().as_str().into(),
+            )))
             .chain(
                 args.autosave_current
                     .map(|v| ("autosave_current", v.into())),
@@ -768,12 +2036,12 @@ impl WalletClient {
     pub async fn create_wallet(
         &self,
         filename: String,
-        password: Option<String>,
+        password: Option<Secret<String>>,
         language: String,
     ) -> anyhow::Result<()> {
         let params = empty()
             .chain(once(("filename", filename.into())))
-            .chain(password.map(|v| ("password", v.into())))
+            .chain(password.map(|v| ("password", v.expose_secret().as_str().into())))
             .chain(once(("language", language.into())));
         self.inner
             .request::<IgnoredAny>("create_wallet", RpcParams::map(params))
@@ -786,11 +2054,11 @@ impl WalletClient {
     pub async fn open_wallet(
         &self,
         filename: String,
-        password: Option<String>,
+        password: Option<Secret<String>>,
     ) -> anyhow::Result<()> {
         let params = empty()
             .chain(once(("filename", filename.into())))
-            .chain(password.map(|v| ("password", v.into())));
+            .chain(password.map(|v| ("password", v.expose_secret().as_str().into())));
 
         self.inner
             .request::<IgnoredAny>("open_wallet", RpcParams::map(params))
@@ -807,23 +2075,99 @@ impl WalletClient {
         Ok(())
     }
 
+    /// Save the wallet file.
+    pub async fn store(&self) -> anyhow::Result<()> {
+        self.inner
+            .request::<IgnoredAny>("store", RpcParams::None)
+            .await?;
+        Ok(())
+    }
+
+    /// Stop the wallet, storing the current state. Note that this shuts down the wallet RPC
+    /// daemon itself.
+    pub async fn stop_wallet(&self) -> anyhow::Result<()> {
+        self.inner
+            .request::<IgnoredAny>("stop_wallet", RpcParams::None)
+            .await?;
+        Ok(())
+    }
+
+    /// Change the password of the currently opened wallet.
+    pub async fn change_wallet_password(
+        &self,
+        old_password: Option<Secret<String>>,
+        new_password: Option<Secret<String>>,
+    ) -> anyhow::Result<()> {
+        let params = empty()
+            .chain(old_password.map(|v| ("old_password", v.expose_secret().as_str().into())))
+            .chain(new_password.map(|v| ("new_password", v.expose_secret().as_str().into())));
+
+        self.inner
+            .request::<IgnoredAny>("change_wallet_password", RpcParams::map(params))
+            .await?;
+        Ok(())
+    }
+
+    /// Connect the wallet RPC to a different daemon. An empty `address` disconnects the wallet
+    /// from the current daemon.
+    pub async fn set_daemon(
+        &self,
+        address: String,
+        options: SetDaemonOptions,
+    ) -> anyhow::Result<()> {
+        let params = empty()
+            .chain(once(("address", address.into())))
+            .chain(options.trusted.map(|v| ("trusted", v.into())))
+            .chain(
+                options
+                    .ssl_support
+                    .map(|v| ("ssl_support", serde_json::to_value(v).unwrap())),
+            )
+            .chain(
+                options
+                    .ssl_private_key_path
+                    .map(|v| ("ssl_private_key_path", v.into())),
+            )
+            .chain(
+                options
+                    .ssl_certificate_path
+                    .map(|v| ("ssl_certificate_path", v.into())),
+            )
+            .chain(options.ssl_ca_file.map(|v| ("ssl_ca_file", v.into())))
+            .chain(
+                options
+                    .ssl_allowed_fingerprints
+                    .map(|v| ("ssl_allowed_fingerprints", v.into())),
+            )
+            .chain(
+                options
+                    .ssl_allow_any_cert
+                    .map(|v| ("ssl_allow_any_cert", v.into())),
+            )
+            .chain(options.username.map(|v| ("username", v.into())))
+            .chain(
+                options
+                    .password
+                    .map(|v| ("password", v.expose_secret().as_str().into())),
+            );
+
+        self.inner
+            .request::<IgnoredAny>("set_daemon", RpcParams::map(params))
+            .await?;
+        Ok(())
+    }
+
     /// Return the wallet's balance.
     pub async fn get_balance(
         &self,
         account_index: u32,
         address_indices: Option<Vec<u32>>,
     ) -> anyhow::Result<BalanceData> {
-        let params = empty()
-            .chain(once(("account_index", account_index.into())))
-            .chain(address_indices.map(|v| {
-                (
-                    "address_indices",
-                    v.into_iter().map(Value::from).collect::<Vec<_>>().into(),
-                )
-            }));
-
         self.inner
-            .request("get_balance", RpcParams::map(params))
+            .request(
+                "get_balance",
+                account_params(account_index, "address_indices", address_indices),
+            )
             .await
     }
 
@@ -834,17 +2178,11 @@ impl WalletClient {
         account: u32,
         addresses: Option<Vec<u32>>,
     ) -> anyhow::Result<AddressData> {
-        let params = empty()
-            .chain(once(("account_index", account.into())))
-            .chain(addresses.map(|v| {
-                (
-                    "address_index",
-                    v.into_iter().map(Value::from).collect::<Vec<_>>().into(),
-                )
-            }));
-
         self.inner
-            .request("get_address", RpcParams::map(params))
+            .request(
+                "get_address",
+                account_params(account, "address_index", addresses),
+            )
             .await
     }
 
@@ -868,6 +2206,25 @@ impl WalletClient {
         })
     }
 
+    /// Analyze a string to determine whether it is a valid Monero address and of which type.
+    /// Unless `any_net_type` is set, a wallet must be opened and only addresses of its network
+    /// are valid. See [`AddressValidation::offline`] to validate addresses without RPC.
+    pub async fn validate_address(
+        &self,
+        address: String,
+        any_net_type: Option<bool>,
+        allow_openalias: Option<bool>,
+    ) -> anyhow::Result<AddressValidation> {
+        let params = empty()
+            .chain(once(("address", address.into())))
+            .chain(any_net_type.map(|v| ("any_net_type", v.into())))
+            .chain(allow_openalias.map(|v| ("allow_openalias", v.into())));
+
+        self.inner
+            .request("validate_address", RpcParams::map(params))
+            .await
+    }
+
     /// Create a new address for an account. Optionally, label the new address.
     pub async fn create_address(
         &self,
@@ -916,6 +2273,86 @@ impl WalletClient {
         self.inner.request("refresh", RpcParams::map(params)).await
     }
 
+    /// Set whether and how often to automatically refresh the current wallet. `period` is
+    /// expressed in seconds.
+    pub async fn auto_refresh(
+        &self,
+        enable: Option<bool>,
+        period: Option<u32>,
+    ) -> anyhow::Result<()> {
+        let params = empty()
+            .chain(enable.map(|v| ("enable", v.into())))
+            .chain(period.map(|v| ("period", v.into())));
+
+        self.inner
+            .request::<IgnoredAny>("auto_refresh", RpcParams::map(params))
+            .await?;
+        Ok(())
+    }
+
+    /// Rescan the blockchain from scratch, losing any information which can not be recovered
+    /// from the blockchain itself. When `hard` is set, the wallet cache is also discarded.
+    pub async fn rescan_blockchain(&self, hard: Option<bool>) -> anyhow::Result<()> {
+        let params = empty().chain(hard.map(|v| ("hard", v.into())));
+
+        self.inner
+            .request::<IgnoredAny>("rescan_blockchain", RpcParams::map(params))
+            .await?;
+        Ok(())
+    }
+
+    /// Rescan the blockchain for spent outputs.
+    pub async fn rescan_spent(&self) -> anyhow::Result<()> {
+        self.inner
+            .request::<IgnoredAny>("rescan_spent", RpcParams::None)
+            .await?;
+        Ok(())
+    }
+
+    /// Have the daemon the wallet is connected to mine to the primary address of the wallet,
+    /// see [`DaemonRpcClient::start_mining`]. The daemon must run on the same machine.
+    pub async fn start_mining(
+        &self,
+        threads_count: u32,
+        do_background_mining: bool,
+        ignore_battery: bool,
+    ) -> anyhow::Result<()> {
+        let params = empty()
+            .chain(once(("threads_count", threads_count.into())))
+            .chain(once(("do_background_mining", do_background_mining.into())))
+            .chain(once(("ignore_battery", ignore_battery.into())));
+
+        self.inner
+            .request::<IgnoredAny>("start_mining", RpcParams::map(params))
+            .await?;
+        Ok(())
+    }
+
+    /// Have the daemon the wallet is connected to stop mining.
+    pub async fn stop_mining(&self) -> anyhow::Result<()> {
+        self.inner
+            .request::<IgnoredAny>("stop_mining", RpcParams::None)
+            .await?;
+        Ok(())
+    }
+
+    /// Scan the given transactions and add the ones relevant to the wallet.
+    pub async fn scan_tx(&self, txids: Vec<CryptoNoteHash>) -> anyhow::Result<()> {
+        let params = once((
+            "txids",
+            txids
+                .into_iter()
+                .map(|s| HashString(s).to_string())
+                .collect::<Vec<_>>()
+                .into(),
+        ));
+
+        self.inner
+            .request::<IgnoredAny>("scan_tx", RpcParams::map(params))
+            .await?;
+        Ok(())
+    }
+
     /// Get all accounts for a wallet. Optionally filter accounts by tag.
     pub async fn get_accounts(&self, tag: Option<String>) -> anyhow::Result<GetAccountsData> {
         let params = empty().chain(tag.map(|v| ("tag", v.into())));
@@ -981,10 +2418,10 @@ impl WalletClient {
     pub async fn query_key(
         &self,
         key_selector: PrivateKeyType,
-    ) -> anyhow::Result<monero::PrivateKey> {
+    ) -> anyhow::Result<Secret<monero::PrivateKey>> {
         #[derive(Deserialize)]
         struct Rsp {
-            key: HashString<Vec<u8>>,
+            key: Secret<HashString<Vec<u8>>>,
         }
 
         let params = empty().chain({
@@ -998,7 +2435,9 @@ impl WalletClient {
             .request::<Rsp>("query_key", RpcParams::map(params))
             .await?;
 
-        Ok(monero::PrivateKey::from_slice(&rsp.key.0)?)
+        Ok(Secret::new(monero::PrivateKey::from_slice(
+            &rsp.key.expose_secret().0,
+        )?))
     }
 
     /// Returns the wallet's current block height.
@@ -1101,6 +2540,43 @@ impl WalletClient {
         self.inner.request("transfer", RpcParams::map(params)).await
     }
 
+    /// Create a transfer without relaying it, to learn its fee and weight beforehand. The
+    /// returned `tx_metadata` can be passed to [`Self::relay_tx`] to broadcast the transaction
+    /// later.
+    pub async fn transfer_dry_run(
+        &self,
+        destinations: HashMap<Address, monero::Amount>,
+        priority: TransferPriority,
+        options: TransferOptions,
+    ) -> anyhow::Result<TransferData> {
+        let options = TransferOptions {
+            do_not_relay: Some(true),
+            ..options
+        };
+
+        self.transfer(destinations, priority, options).await
+    }
+
+    /// Estimate the size and weight of a transaction with the given number of inputs and
+    /// outputs.
+    pub async fn estimate_tx_size_and_weight(
+        &self,
+        n_inputs: u32,
+        n_outputs: u32,
+        ring_size: Option<u32>,
+        rct: Option<bool>,
+    ) -> anyhow::Result<TxSizeAndWeight> {
+        let params = empty()
+            .chain(once(("n_inputs", n_inputs.into())))
+            .chain(once(("n_outputs", n_outputs.into())))
+            .chain(ring_size.map(|v| ("ring_size", v.into())))
+            .chain(rct.map(|v| ("rct", v.into())));
+
+        self.inner
+            .request("estimate_tx_size_and_weight", RpcParams::map(params))
+            .await
+    }
+
     /// Sign a transaction created on a read-only wallet (in cold-signing process).
     pub async fn sign_transfer(
         &self,
@@ -1177,6 +2653,43 @@ impl WalletClient {
             .await
     }
 
+    /// Freeze a single output by key image so it will not be used in transfers.
+    pub async fn freeze(&self, key_image: String) -> anyhow::Result<()> {
+        let params = once(("key_image", key_image.into()));
+
+        self.inner
+            .request::<IgnoredAny>("freeze", RpcParams::map(params))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Thaw a single output by key image so it may be used again.
+    pub async fn thaw(&self, key_image: String) -> anyhow::Result<()> {
+        let params = once(("key_image", key_image.into()));
+
+        self.inner
+            .request::<IgnoredAny>("thaw", RpcParams::map(params))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Checks whether a given output is currently frozen by key image.
+    pub async fn frozen(&self, key_image: String) -> anyhow::Result<bool> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            frozen: bool,
+        }
+
+        let params = once(("key_image", key_image.into()));
+
+        self.inner
+            .request::<Rsp>("frozen", RpcParams::map(params))
+            .await
+            .map(|rsp| rsp.frozen)
+    }
+
     /// Returns a list of transfers.
     pub async fn get_transfers(
         &self,
@@ -1331,7 +2844,7 @@ impl WalletClient {
     pub async fn check_tx_key(
         &self,
         txid: CryptoNoteHash,
-        tx_key: Vec<u8>,
+        tx_key: Secret<Vec<u8>>,
         address: Address,
     ) -> anyhow::Result<(u64, bool, Amount)> {
         #[derive(Deserialize)]
@@ -1344,7 +2857,7 @@ impl WalletClient {
 
         let params = empty()
             .chain(once(("txid", HashString(txid).to_string().into())))
-            .chain(once(("tx_key", HashString(tx_key).to_string().into())))
+            .chain(once(("tx_key", hex::encode(tx_key.expose_secret()).into())))
             .chain(once(("address", address.to_string().into())));
 
         let rsp = self
@@ -1406,10 +2919,7 @@ impl WalletClient {
             .request::<Rsp>("get_version", RpcParams::None)
             .await?;
 
-        let major = version.version >> 16;
-        let minor = version.version - (major << 16);
-
-        Ok((u16::try_from(major)?, u16::try_from(minor)?))
+        split_version(version.version)
     }
 
     /// Returns an attribute as a string or an error when there is no attribute for the given key
@@ -1563,6 +3073,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialize_ssl_support() {
+        let ssl_supports = vec![
+            SslSupport::Autodetect,
+            SslSupport::Enabled,
+            SslSupport::Disabled,
+        ];
+        assert_ser_tokens(
+            &ssl_supports,
+            &[
+                Token::Seq { len: Some(3) },
+                Token::Str("autodetect"),
+                Token::Str("enabled"),
+                Token::Str("disabled"),
+                Token::SeqEnd,
+            ],
+        );
+    }
+
     #[test]
     fn ser_der_for_transfer_priority() {
         let transfer_priorities = vec![
@@ -1591,4 +3120,458 @@ mod tests {
             "Invalid variant 4, expected 0-3",
         );
     }
+
+    #[test]
+    fn ser_der_for_hard_fork_state() {
+        let states = vec![
+            HardForkState::LikelyForked,
+            HardForkState::UpdateNeeded,
+            HardForkState::Ready,
+        ];
+        assert_tokens(
+            &states,
+            &[
+                Token::Seq { len: Some(3) },
+                Token::U8(0),
+                Token::U8(1),
+                Token::U8(2),
+                Token::SeqEnd,
+            ],
+        );
+        assert_de_tokens_error::<HardForkState>(&[Token::U8(3)], "Invalid variant 3, expected 0-2");
+    }
+
+    #[tokio::test]
+    async fn wait_until_synced() {
+        let server = mock::MockServer::start().await.unwrap();
+        let polls = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let counter = polls.clone();
+        server.json_rpc_handler("sync_info", move |_| {
+            let height = 100 + 50 * counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(json!({
+                "height": height,
+                "target_height": 200,
+                "next_needed_pruning_seed": 0,
+                "overview": "[]",
+                "peers": [],
+                "spans": [{
+                    "connection_id": "3e1a4d2f5b6c4d8e9f0a1b2c3d4e5f60",
+                    "nblocks": 50,
+                    "rate": 1024,
+                    "remote_address": "95.216.203.255:18080",
+                    "size": 51200,
+                    "speed": 100,
+                    "start_block_height": height,
+                }],
+                "status": "OK",
+                "untrusted": false,
+            }))
+        });
+        let daemon = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon();
+
+        let mut heights = vec![];
+        let info = daemon
+            .wait_until_synced(Duration::from_millis(1), |info| heights.push(info.height))
+            .await
+            .unwrap();
+        assert_eq!(heights, vec![100, 150, 200]);
+        assert!(info.is_synced());
+        assert_eq!(info.spans[0].start_block_height, 200);
+    }
+
+    #[tokio::test]
+    async fn wait_until_synced_at_tip_without_target() {
+        let server = mock::MockServer::start().await.unwrap();
+        server.json_rpc(
+            "sync_info",
+            json!({
+                "height": 1,
+                "target_height": 0,
+                "next_needed_pruning_seed": 0,
+                "overview": "[]",
+                "peers": [],
+                "spans": [],
+                "status": "OK",
+            }),
+        );
+        let daemon = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon();
+
+        let info = daemon.sync_info().await.unwrap();
+        assert!(info.is_synced());
+
+        let mut polls = 0;
+        let wait = daemon.wait_until_synced(Duration::from_millis(1), |_| polls += 1);
+        let info = tokio::time::timeout(Duration::from_secs(1), wait)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.height, 1);
+        assert_eq!(polls, 1);
+    }
+
+    #[tokio::test]
+    async fn emission_and_output_statistics() {
+        let server = mock::MockServer::start().await.unwrap();
+        server.json_rpc(
+            "get_coinbase_tx_sum",
+            json!({
+                "emission_amount": 1_000,
+                "emission_amount_top64": 1,
+                "fee_amount": 20,
+                "fee_amount_top64": 0,
+                "wide_emission_amount": "0x100000000000003e8",
+                "wide_fee_amount": "0x14",
+                "status": "OK",
+            }),
+        );
+        server.json_rpc_handler("get_output_distribution", |params| {
+            let params: Value = params.parse()?;
+            assert_eq!(params["amounts"], json!([0]));
+            assert!(params.get("binary").is_none());
+            Ok(json!({
+                "distributions": [{
+                    "amount": 0,
+                    "base": 0,
+                    "distribution": [1, 300, 3],
+                    "start_height": 10,
+                }],
+                "status": "OK",
+            }))
+        });
+        server.binary_rpc_handler("get_output_distribution.bin", |body| {
+            use epee::{Section, Value::*};
+
+            let request = epee::from_bytes(&body).unwrap();
+            assert!(matches!(
+                request.get("amounts"),
+                Some(Array(amounts)) if matches!(amounts[..], [UInt(0)])
+            ));
+            assert!(request.bool("binary").unwrap());
+
+            // Values of 128 and more are packed over several bytes, which are not valid UTF-8.
+            let distribution = if request.bool("compress").unwrap() {
+                (
+                    "compressed_data".to_string(),
+                    Blob(vec![0x01, 0xac, 0x02, 0xff, 0x01]),
+                )
+            } else {
+                (
+                    "distribution".to_string(),
+                    Blob(
+                        [1u64, 300, 255]
+                            .iter()
+                            .flat_map(|v| v.to_le_bytes())
+                            .collect(),
+                    ),
+                )
+            };
+            let distribution = Section(vec![
+                ("amount".to_string(), UInt(0)),
+                ("start_height".to_string(), UInt(10)),
+                ("binary".to_string(), Bool(true)),
+                (
+                    "compress".to_string(),
+                    request.get("compress").unwrap().clone(),
+                ),
+                distribution,
+                ("base".to_string(), UInt(0)),
+            ]);
+            epee::to_bytes(&Section(vec![
+                (
+                    "distributions".to_string(),
+                    Array(vec![Object(distribution)]),
+                ),
+                ("status".to_string(), Blob(b"OK".to_vec())),
+                ("untrusted".to_string(), Bool(false)),
+            ]))
+            .unwrap()
+        });
+        let daemon = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon();
+
+        let sum = daemon.get_coinbase_tx_sum(0, 100).await.unwrap();
+        assert_eq!(sum.emission_amount, Amount::from_pico(1_000));
+        assert_eq!(sum.wide_emission_amount, (1 << 64) + 1_000);
+        assert_eq!(sum.wide_fee_amount, 20);
+
+        let distributions = daemon
+            .get_output_distribution(OutputDistributionArgs {
+                amounts: vec![Amount::ZERO],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(distributions.len(), 1);
+        assert_eq!(distributions[0].distribution, vec![1, 300, 3]);
+        assert_eq!(distributions[0].start_height, 10);
+
+        for compress in [false, true] {
+            let distributions = daemon
+                .get_output_distribution(OutputDistributionArgs {
+                    amounts: vec![Amount::ZERO],
+                    binary: true,
+                    compress,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            assert_eq!(distributions.len(), 1);
+            assert_eq!(distributions[0].distribution, vec![1, 300, 255]);
+            assert_eq!(distributions[0].start_height, 10);
+        }
+    }
+
+    #[tokio::test]
+    async fn get_outs() {
+        use epee::{Section, Value::*};
+
+        let key = monero::PublicKey::from_private_key(
+            &"8ae33e57aee12fa4ad5b42a3ab093d9f3cb7f9be68b112a85f83275bcc5a190b"
+                .parse()
+                .unwrap(),
+        );
+        let expected = OutKey {
+            key,
+            mask: monero::util::ringct::Key { key: [2; 32] },
+            unlocked: true,
+            height: 10,
+            txid: CryptoNoteHash([3; 32]),
+        };
+
+        let server = mock::MockServer::start().await.unwrap();
+        server.other_rpc(
+            "get_outs",
+            json!({
+                "outs": [{
+                    "key": key.to_string(),
+                    "mask": hex::encode([2; 32]),
+                    "unlocked": true,
+                    "height": 10,
+                    "txid": hex::encode([3; 32]),
+                }],
+                "status": "OK",
+                "untrusted": false,
+            }),
+        );
+        server.binary_rpc_handler("get_outs.bin", move |body| {
+            let request = epee::from_bytes(&body).unwrap();
+            let outputs = request.objects("outputs").unwrap();
+            assert_eq!(outputs[0].u64("amount").unwrap(), 0);
+            assert_eq!(outputs[0].u64("index").unwrap(), 42);
+            assert!(request.bool("get_txid").unwrap());
+
+            let out = Section(vec![
+                ("key".to_string(), Blob(key.as_bytes().to_vec())),
+                ("mask".to_string(), Blob(vec![2; 32])),
+                ("unlocked".to_string(), Bool(true)),
+                ("height".to_string(), UInt(10)),
+                ("txid".to_string(), Blob(vec![3; 32])),
+            ]);
+            epee::to_bytes(&Section(vec![
+                ("outs".to_string(), Array(vec![Object(out)])),
+                ("status".to_string(), Blob(b"OK".to_vec())),
+                ("untrusted".to_string(), Bool(false)),
+            ]))
+            .unwrap()
+        });
+        let daemon_rpc = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon_rpc();
+
+        let outs = daemon_rpc.get_outs(vec![(Amount::ZERO, 42)]).await.unwrap();
+        assert_eq!(outs, vec![expected]);
+        assert_eq!(
+            server.requests()[0].params,
+            json!({ "outputs": [{ "amount": 0, "index": 42 }], "get_txid": true })
+        );
+
+        let outs = daemon_rpc
+            .get_outs_bin(vec![(Amount::ZERO, 42)])
+            .await
+            .unwrap();
+        assert_eq!(outs, vec![expected]);
+    }
+
+    #[tokio::test]
+    async fn mining() {
+        let address: Address = "4AdUndXHHZ6cfufTMvppY6JwXNouMBzSkbLYfpAV5Usx3skxNgYeYTRj5UzqtReoS44qo9mtmXCqY45DJ852K5Jv2684Rge".parse().unwrap();
+        let status = |active: bool| {
+            json!({
+                "active": active,
+                "address": if active { address.to_string() } else { String::new() },
+                "bg_idle_threshold": 90,
+                "bg_ignore_battery": false,
+                "bg_min_idle_seconds": 10,
+                "bg_target": 40,
+                "block_reward": 600000000000u64,
+                "block_target": 120,
+                "difficulty": 1,
+                "difficulty_top64": 1,
+                "is_background_mining_enabled": false,
+                "pow_algorithm": "RandomX",
+                "speed": if active { 1200 } else { 0 },
+                "status": "OK",
+                "threads_count": if active { 2 } else { 0 },
+                "untrusted": false,
+                "wide_difficulty": "0x10000000000000001",
+            })
+        };
+
+        let server = mock::MockServer::start().await.unwrap();
+        server
+            .other_rpc(
+                "start_mining",
+                json!({ "status": "OK", "untrusted": false }),
+            )
+            .other_rpc("mining_status", status(false));
+        let daemon_rpc = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon_rpc();
+
+        let mining_status = daemon_rpc.mining_status().await.unwrap();
+        assert!(!mining_status.active);
+        assert_eq!(mining_status.address, None);
+        assert_eq!(mining_status.difficulty, (1 << 64) + 1);
+        assert_eq!(mining_status.block_reward, Amount::from_xmr(0.6).unwrap());
+
+        daemon_rpc
+            .start_mining(address, 2, false, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            server.requests()[1].params,
+            json!({
+                "miner_address": address.to_string(),
+                "threads_count": 2,
+                "do_background_mining": false,
+                "ignore_battery": true,
+            })
+        );
+
+        server.other_rpc("mining_status", status(true));
+        let mining_status = daemon_rpc.mining_status().await.unwrap();
+        assert!(mining_status.active);
+        assert_eq!(mining_status.address, Some(address));
+        assert_eq!(mining_status.threads_count, 2);
+
+        // the node may refuse to mine, e.g. to a subaddress
+        server.other_rpc("stop_mining", json!({ "status": "Mining never started" }));
+        assert!(daemon_rpc.stop_mining().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn pool_support() {
+        let hash = |byte: u8| hex::encode([byte; 32]);
+        let server = mock::MockServer::start().await.unwrap();
+        server
+            .json_rpc_handler("get_block_template", move |params| {
+                let params: Value = params.parse()?;
+                assert_eq!(params["extra_nonce"], "0102");
+                assert_eq!(params["prev_block"], hash(1));
+                assert!(params.get("reserve_size").is_none());
+                Ok(json!({
+                    "blockhashing_blob": "00",
+                    "blocktemplate_blob": "01",
+                    "difficulty": 1,
+                    "difficulty_top64": 1,
+                    "expected_reward": 600000000000u64,
+                    "height": 3000,
+                    "next_seed_hash": "",
+                    "prev_hash": hash(1),
+                    "reserved_offset": 0,
+                    "seed_hash": hash(2),
+                    "seed_height": 2048,
+                    "status": "OK",
+                    "untrusted": false,
+                    "wide_difficulty": "0x10000000000000001",
+                }))
+            })
+            .json_rpc(
+                "get_miner_data",
+                json!({
+                    "major_version": 16,
+                    "height": 3000,
+                    "prev_id": hash(1),
+                    "seed_hash": hash(2),
+                    "difficulty": "0x1bc16d674ec80000",
+                    "median_weight": 300000,
+                    "already_generated_coins": 18446744073709551615u64,
+                    "tx_backlog": [{ "id": hash(3), "weight": 1500, "fee": 30000000 }],
+                    "status": "OK",
+                    "untrusted": false,
+                }),
+            )
+            .json_rpc_handler("calc_pow", move |params| {
+                let params: Value = params.parse()?;
+                assert_eq!(params["block_blob"], "00");
+                assert_eq!(params["seed_hash"], hash(2));
+                Ok(json!(hash(4)))
+            })
+            .json_rpc_handler("add_aux_pow", move |params| {
+                let params: Value = params.parse()?;
+                assert_eq!(
+                    params["aux_pow"],
+                    json!([{ "id": hash(5), "hash": hash(6) }])
+                );
+                Ok(json!({
+                    "blocktemplate_blob": "02",
+                    "blockhashing_blob": "03",
+                    "merkle_root": hash(6),
+                    "merkle_tree_depth": 0,
+                    "aux_pow": [{ "id": hash(5), "hash": hash(6) }],
+                    "status": "OK",
+                    "untrusted": false,
+                }))
+            });
+        let daemon = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon();
+
+        let template = daemon
+            .get_block_template_with_args(GetBlockTemplateArgs {
+                wallet_address: "4AdUndXHHZ6cfufTMvppY6JwXNouMBzSkbLYfpAV5Usx3skxNgYeYTRj5UzqtReoS44qo9mtmXCqY45DJ852K5Jv2684Rge".parse().unwrap(),
+                reserve_size: None,
+                extra_nonce: Some(vec![1, 2]),
+                prev_block: Some(BlockHash::repeat_byte(1)),
+            })
+            .await
+            .unwrap();
+        assert_eq!(template.next_seed_hash, None);
+        assert_eq!(template.seed_hash.0, CryptoNoteHash([2; 32]));
+        assert_eq!(template.seed_height, 2048);
+        assert_eq!(template.wide_difficulty, (1 << 64) + 1);
+        assert_eq!(
+            serde_json::to_value(&template).unwrap()["wide_difficulty"],
+            "0x10000000000000001"
+        );
+
+        let miner_data = daemon.get_miner_data().await.unwrap();
+        assert_eq!(miner_data.difficulty, 0x1bc16d674ec80000);
+        assert_eq!(miner_data.tx_backlog[0].fee, Amount::from_pico(30000000));
+
+        let pow = daemon
+            .calc_pow(16, 3000, vec![0], CryptoNoteHash([2; 32]))
+            .await
+            .unwrap();
+        assert_eq!(pow, CryptoNoteHash([4; 32]));
+
+        let aux_pow = vec![AuxPow {
+            id: HashString(CryptoNoteHash([5; 32])),
+            hash: HashString(CryptoNoteHash([6; 32])),
+        }];
+        let aux_template = daemon.add_aux_pow(vec![1], aux_pow.clone()).await.unwrap();
+        assert_eq!(aux_template.aux_pow, aux_pow);
+        assert_eq!(aux_template.blocktemplate_blob.0, vec![2]);
+    }
 }
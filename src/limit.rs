@@ -0,0 +1,270 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::Instant,
+};
+
+/// Token-bucket rate limit, see [`RpcClientBuilder::rate_limit`].
+///
+/// The bucket holds up to `burst` tokens and is refilled with `requests` tokens every `per`.
+/// Each request takes one token, and waits for the next one when the bucket is empty.
+///
+/// [`RpcClientBuilder::rate_limit`]: crate::RpcClientBuilder::rate_limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    requests: u32,
+    per: Duration,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// Allow `requests` requests every `per`, with bursts of up to `requests` requests.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        let requests = requests.max(1);
+        Self {
+            requests,
+            per,
+            burst: requests,
+        }
+    }
+
+    /// Allow `requests` requests per second.
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    /// Maximum number of requests sent at once after a period of inactivity.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+/// Limits configured on an `RpcClientBuilder`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Limits {
+    pub(crate) max_in_flight: Option<usize>,
+    pub(crate) rate_limit: Option<RateLimit>,
+    pub(crate) methods: HashMap<String, (Option<usize>, Option<RateLimit>)>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new((limit.burst as f64, Instant::now())),
+        }
+    }
+
+    /// Take one token, waiting for the bucket to refill if it is empty.
+    async fn take(&self) {
+        let rate = self.limit.requests as f64 / self.limit.per.as_secs_f64();
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let (tokens, last) = *state;
+                let tokens = (tokens + now.duration_since(last).as_secs_f64() * rate)
+                    .min(self.limit.burst as f64);
+                if tokens >= 1.0 {
+                    *state = (tokens - 1.0, now);
+                    return;
+                }
+                *state = (tokens, now);
+                Duration::from_secs_f64((1.0 - tokens) / rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Limit {
+    in_flight: Option<Semaphore>,
+    rate: Option<TokenBucket>,
+}
+
+impl Limit {
+    fn new(max_in_flight: Option<usize>, rate_limit: Option<RateLimit>) -> Self {
+        Self {
+            in_flight: max_in_flight.map(|n| Semaphore::new(n.max(1))),
+            rate: rate_limit.map(TokenBucket::new),
+        }
+    }
+
+    /// Wait for a free in-flight slot, then for a token: a request waiting for a slot does not
+    /// hold a token, so that requests released together by the semaphore are still spaced out
+    /// by the rate limit.
+    async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = match &self.in_flight {
+            // the semaphore is never closed
+            Some(semaphore) => semaphore.acquire().await.ok(),
+            None => None,
+        };
+        if let Some(rate) = &self.rate {
+            rate.take().await;
+        }
+        permit
+    }
+}
+
+/// Limits shared by all the clones of a client.
+#[derive(Debug)]
+pub(crate) struct Limiter {
+    global: Limit,
+    methods: HashMap<String, Limit>,
+}
+
+/// Permits held while a request is in flight.
+pub(crate) struct LimitGuard<'a> {
+    _method: Option<SemaphorePermit<'a>>,
+    _global: Option<SemaphorePermit<'a>>,
+}
+
+impl Limiter {
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self {
+            global: Limit::new(limits.max_in_flight, limits.rate_limit),
+            methods: limits
+                .methods
+                .into_iter()
+                .map(|(method, (max_in_flight, rate_limit))| {
+                    (method, Limit::new(max_in_flight, rate_limit))
+                })
+                .collect(),
+        }
+    }
+
+    /// Wait until a request to `method` is allowed by the limits of the method, then by the
+    /// global limits.
+    pub(crate) async fn acquire(&self, method: &str) -> LimitGuard<'_> {
+        let method = match self.methods.get(method) {
+            Some(limit) => limit.acquire().await,
+            None => None,
+        };
+        let global = self.global.acquire().await;
+
+        LimitGuard {
+            _method: method,
+            _global: global,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[tokio::test]
+    async fn limiter_max_in_flight() {
+        let mut limits = Limits {
+            max_in_flight: Some(4),
+            ..Default::default()
+        };
+        limits
+            .methods
+            .insert("get_block".to_string(), (Some(2), None));
+        let limiter = Arc::new(Limiter::new(limits));
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_seen = Arc::new(AtomicUsize::new(0));
+        let handles = (0..10)
+            .map(|_| {
+                let (limiter, in_flight, max_seen) =
+                    (limiter.clone(), in_flight.clone(), max_seen.clone());
+                tokio::spawn(async move {
+                    let _guard = limiter.acquire("get_block").await;
+                    let n = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_seen.fetch_max(n, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(max_seen.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn limiter_rate_limit() {
+        tokio::time::pause();
+        let limiter = Limiter::new(Limits {
+            rate_limit: Some(RateLimit::new(10, Duration::from_millis(100)).burst(5)),
+            ..Default::default()
+        });
+
+        // the burst goes through right away, then 1 request every 10ms
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire("get_block").await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        for _ in 0..5 {
+            limiter.acquire("get_block").await;
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(49) && elapsed <= Duration::from_millis(51));
+    }
+
+    #[tokio::test]
+    async fn limiter_waits_for_in_flight_slot_before_token() {
+        tokio::time::pause();
+        let limiter = Arc::new(Limiter::new(Limits {
+            max_in_flight: Some(1),
+            rate_limit: Some(RateLimit::new(1, Duration::from_millis(100))),
+            ..Default::default()
+        }));
+        let start = Instant::now();
+
+        // two requests queue behind a slow one, while the bucket refills
+        let guard = limiter.acquire("get_block").await;
+        let handles = (0..2)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move {
+                    let _guard = limiter.acquire("get_block").await;
+                    start.elapsed()
+                })
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..10 {
+            tokio::time::advance(Duration::from_millis(100)).await;
+        }
+        drop(guard);
+
+        // the first goes with the refilled token, the second waits for the next one
+        let mut sent = vec![];
+        for handle in handles {
+            sent.push(handle.await.unwrap());
+        }
+        assert_eq!(sent[0], Duration::from_secs(1));
+        assert!(sent[1] >= Duration::from_millis(1099));
+    }
+}
@@ -0,0 +1,504 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! In-process mock of `monerod` and `monero-wallet-rpc`, to test code using the clients offline
+//! and deterministically.
+//!
+//! [`MockServer`] listens on a local port and answers on `/json_rpc` and on the endpoints called
+//! with their own extension, such as `/get_transactions` or the binary `/get_outs.bin`, with
+//! canned responses or with the result of programmable handlers.
+//!
+//! ```rust
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! use monero_rpc::{mock::MockServer, RpcClientBuilder};
+//! use serde_json::json;
+//!
+//! let server = MockServer::start().await?;
+//! server.json_rpc("get_height", json!({ "height": 1234 }));
+//!
+//! let wallet = RpcClientBuilder::new().build(server.url())?.wallet();
+//! assert_eq!(wallet.get_height().await?.get(), 1234);
+//! # Ok(())
+//! # }
+//! ```
+
+use jsonrpc_core::types::{response::Output, Call, Id, Params, Request, Version};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use tracing::*;
+
+type JsonRpcHandler = Arc<dyn Fn(Params) -> jsonrpc_core::Result<Value> + Send + Sync>;
+type OtherRpcHandler = Arc<dyn Fn(Value) -> Value + Send + Sync>;
+type BinaryRpcHandler = Arc<dyn Fn(Vec<u8>) -> Vec<u8> + Send + Sync>;
+
+/// Request received by a [`MockServer`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockRequest {
+    /// JSON-RPC method for requests on `/json_rpc`, endpoint name otherwise, e.g.
+    /// `get_transactions`.
+    pub method: String,
+    /// Parameters of the request, `Null` if there are none. The body of requests to binary
+    /// endpoints is given as a hex string.
+    pub params: Value,
+}
+
+#[derive(Default)]
+struct Handlers {
+    json_rpc: HashMap<String, JsonRpcHandler>,
+    other_rpc: HashMap<String, OtherRpcHandler>,
+    binary_rpc: HashMap<String, BinaryRpcHandler>,
+    requests: Vec<MockRequest>,
+    http_requests: usize,
+    reject_batches: bool,
+}
+
+/// Local HTTP server mocking `monerod` or `monero-wallet-rpc`, see the [module
+/// documentation](self).
+///
+/// JSON-RPC methods without a response answer with a "Method not found" error, other endpoints
+/// without a response answer with a 404 status. The server stops when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    handlers: Arc<Mutex<Handlers>>,
+    task: JoinHandle<()>,
+    connections: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl std::fmt::Debug for MockServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockServer")
+            .field("addr", &self.addr)
+            .finish()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+        // also close the connections kept alive by clients
+        for connection in self.connections.lock().unwrap().drain(..) {
+            connection.abort();
+        }
+    }
+}
+
+impl MockServer {
+    /// Start a server on a free local port. Must be called from within a Tokio runtime.
+    pub async fn start() -> anyhow::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let handlers = Arc::new(Mutex::new(Handlers::default()));
+
+        let connections = Arc::new(Mutex::new(Vec::new()));
+
+        let task = tokio::spawn({
+            let handlers = handlers.clone();
+            let connections = connections.clone();
+            async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, _)) => {
+                            let connection =
+                                tokio::spawn(serve_connection(stream, handlers.clone()));
+                            connections.lock().unwrap().push(connection);
+                        }
+                        Err(e) => warn!("Mock server failed to accept a connection: {}", e),
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            handlers,
+            task,
+            connections,
+        })
+    }
+
+    /// Address to give to [`RpcClientBuilder::build`](crate::RpcClientBuilder::build).
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Answer the JSON-RPC `method` with `result`.
+    pub fn json_rpc(&self, method: impl Into<String>, result: Value) -> &Self {
+        self.json_rpc_handler(method, move |_| Ok(result.clone()))
+    }
+
+    /// Answer the JSON-RPC `method` with an error.
+    pub fn json_rpc_error(
+        &self,
+        method: impl Into<String>,
+        code: i64,
+        message: impl Into<String>,
+    ) -> &Self {
+        let error = jsonrpc_core::Error {
+            code: code.into(),
+            message: message.into(),
+            data: None,
+        };
+        self.json_rpc_handler(method, move |_| Err(error.clone()))
+    }
+
+    /// Answer the JSON-RPC `method` with the result of `handler`, called with the parameters of
+    /// each request.
+    pub fn json_rpc_handler<F>(&self, method: impl Into<String>, handler: F) -> &Self
+    where
+        F: Fn(Params) -> jsonrpc_core::Result<Value> + Send + Sync + 'static,
+    {
+        self.handlers
+            .lock()
+            .unwrap()
+            .json_rpc
+            .insert(method.into(), Arc::new(handler));
+        self
+    }
+
+    /// Answer the endpoint `method`, e.g. `get_transactions`, with `response`.
+    pub fn other_rpc(&self, method: impl Into<String>, response: Value) -> &Self {
+        self.other_rpc_handler(method, move |_| response.clone())
+    }
+
+    /// Answer the endpoint `method` with the result of `handler`, called with the body of each
+    /// request.
+    pub fn other_rpc_handler<F>(&self, method: impl Into<String>, handler: F) -> &Self
+    where
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        self.handlers
+            .lock()
+            .unwrap()
+            .other_rpc
+            .insert(method.into(), Arc::new(handler));
+        self
+    }
+
+    /// Answer the binary endpoint `method`, e.g. `get_outs.bin`, with the result of `handler`,
+    /// called with the body of each request.
+    pub fn binary_rpc_handler<F>(&self, method: impl Into<String>, handler: F) -> &Self
+    where
+        F: Fn(Vec<u8>) -> Vec<u8> + Send + Sync + 'static,
+    {
+        self.handlers
+            .lock()
+            .unwrap()
+            .binary_rpc
+            .insert(method.into(), Arc::new(handler));
+        self
+    }
+
+    /// Answer JSON-RPC batches with a parse error, like a node not supporting them.
+    pub fn reject_batches(&self, reject: bool) -> &Self {
+        self.handlers.lock().unwrap().reject_batches = reject;
+        self
+    }
+
+    /// Requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.handlers.lock().unwrap().requests.clone()
+    }
+
+    /// Number of HTTP requests received so far. Unlike [`MockServer::requests`], a JSON-RPC batch
+    /// counts as one request.
+    pub fn http_requests(&self) -> usize {
+        self.handlers.lock().unwrap().http_requests
+    }
+}
+
+/// Serve the HTTP/1.1 requests of one connection until the client closes it.
+async fn serve_connection(mut stream: TcpStream, handlers: Arc<Mutex<Handlers>>) {
+    let mut buf = Vec::new();
+    loop {
+        let (path, body) = match read_request(&mut stream, &mut buf).await {
+            Ok(Some(request)) => request,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Mock server failed to read a request: {}", e);
+                return;
+            }
+        };
+
+        let (status, body) = handle_request(&path, &body, &handlers);
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            status,
+            body.len()
+        );
+        if let Err(e) = async {
+            stream.write_all(head.as_bytes()).await?;
+            stream.write_all(&body).await
+        }
+        .await
+        {
+            warn!("Mock server failed to write a response: {}", e);
+            return;
+        }
+    }
+}
+
+/// Read one request from `stream` and return its path and body, `None` if the connection was
+/// closed. Bytes read past the request are kept in `buf` for the next one.
+async fn read_request(
+    stream: &mut TcpStream,
+    buf: &mut Vec<u8>,
+) -> anyhow::Result<Option<(String, Vec<u8>)>> {
+    let head_len = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let mut chunk = [0; 4096];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return if buf.is_empty() {
+                Ok(None)
+            } else {
+                Err(anyhow::Error::msg("Connection closed mid-request"))
+            };
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_len]).into_owned();
+    let mut lines = head.lines();
+    let path = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .ok_or_else(|| anyhow::Error::msg("Invalid request line"))?
+        .to_string();
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.trim().parse::<usize>())
+        .transpose()?
+        .unwrap_or(0);
+
+    while buf.len() < head_len + content_length {
+        let mut chunk = [0; 4096];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(anyhow::Error::msg("Connection closed mid-request"));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let body = buf[head_len..head_len + content_length].to_vec();
+    buf.drain(..head_len + content_length);
+    Ok(Some((path, body)))
+}
+
+/// Answer one JSON-RPC method call, alone or in a batch.
+fn handle_json_rpc(call: Call, handlers: &Mutex<Handlers>) -> Output {
+    let call = match call {
+        Call::MethodCall(call) => call,
+        _ => {
+            return Output::from(
+                Err(jsonrpc_core::Error::invalid_request()),
+                Id::Null,
+                Some(Version::V2),
+            )
+        }
+    };
+    let handler = {
+        let mut handlers = handlers.lock().unwrap();
+        handlers.requests.push(MockRequest {
+            method: call.method.clone(),
+            params: serde_json::to_value(&call.params).unwrap_or(Value::Null),
+        });
+        handlers.json_rpc.get(&call.method).cloned()
+    };
+    let result = match handler {
+        Some(handler) => handler(call.params),
+        None => Err(jsonrpc_core::Error::method_not_found()),
+    };
+    Output::from(result, call.id, Some(Version::V2))
+}
+
+/// Dispatch a request to its handler and return the HTTP status and body of the response.
+fn handle_request(path: &str, body: &[u8], handlers: &Mutex<Handlers>) -> (&'static str, Vec<u8>) {
+    handlers.lock().unwrap().http_requests += 1;
+    let method = path.trim_start_matches('/');
+
+    if method == "json_rpc" {
+        let parse_error = || {
+            Output::from(
+                Err(jsonrpc_core::Error::parse_error()),
+                Id::Null,
+                Some(Version::V2),
+            )
+        };
+        let body = match serde_json::from_slice::<Request>(body) {
+            Ok(Request::Single(call)) => serde_json::to_vec(&handle_json_rpc(call, handlers)),
+            Ok(Request::Batch(_)) if handlers.lock().unwrap().reject_batches => {
+                serde_json::to_vec(&parse_error())
+            }
+            Ok(Request::Batch(calls)) => serde_json::to_vec(
+                &calls
+                    .into_iter()
+                    .map(|call| handle_json_rpc(call, handlers))
+                    .collect::<Vec<_>>(),
+            ),
+            Err(_) => serde_json::to_vec(&parse_error()),
+        };
+        return ("200 OK", body.unwrap_or_default());
+    }
+
+    let binary_handler = handlers.lock().unwrap().binary_rpc.get(method).cloned();
+    if let Some(handler) = binary_handler {
+        handlers.lock().unwrap().requests.push(MockRequest {
+            method: method.to_string(),
+            params: hex::encode(body).into(),
+        });
+        return ("200 OK", handler(body.to_vec()));
+    }
+
+    let params = serde_json::from_slice::<Value>(body).unwrap_or(Value::Null);
+    let handler = {
+        let mut handlers = handlers.lock().unwrap();
+        handlers.requests.push(MockRequest {
+            method: method.to_string(),
+            params: params.clone(),
+        });
+        handlers.other_rpc.get(method).cloned()
+    };
+    match handler {
+        Some(handler) => (
+            "200 OK",
+            serde_json::to_vec(&handler(params)).unwrap_or_default(),
+        ),
+        None => ("404 Not Found", Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RpcClientBuilder;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[tokio::test]
+    async fn mock_server_json_rpc() {
+        let server = MockServer::start().await.unwrap();
+        let height = Arc::new(AtomicU64::new(100));
+        server
+            .json_rpc("get_version", json!({ "version": 196621 }))
+            .json_rpc_handler("get_block_count", {
+                let height = height.clone();
+                move |_| {
+                    Ok(json!({
+                        "count": height.fetch_add(1, Ordering::Relaxed),
+                        "status": "OK",
+                    }))
+                }
+            })
+            .json_rpc_error("on_get_block_hash", -2, "Requested block height too big");
+
+        let daemon = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon();
+
+        assert_eq!(daemon.get_version().await.unwrap(), (3, 13));
+        assert_eq!(daemon.get_block_count().await.unwrap().get(), 100);
+        assert_eq!(daemon.get_block_count().await.unwrap().get(), 101);
+        assert_eq!(
+            daemon
+                .on_get_block_hash(1000)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Server error: Requested block height too big"
+        );
+        assert_eq!(
+            daemon
+                .get_block_header(crate::GetBlockHeaderSelector::Last)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Method not found: Method not found"
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "get_version");
+        assert_eq!(requests[3].method, "on_get_block_hash");
+        assert_eq!(requests[3].params, json!([1000]));
+    }
+
+    #[tokio::test]
+    async fn mock_server_closes_connections_when_dropped() {
+        let server = MockServer::start().await.unwrap();
+        server.json_rpc("get_version", json!({ "version": 196621 }));
+        let daemon = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon();
+        daemon.get_version().await.unwrap();
+
+        // the connection kept alive by the client is not served anymore
+        drop(server);
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert!(daemon.get_version().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn mock_server_other_rpc() {
+        let server = MockServer::start().await.unwrap();
+        let daemon_rpc = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon_rpc();
+
+        // endpoints without a response answer with a 404 status and no body
+        assert!(daemon_rpc
+            .get_transactions(vec![], None, None)
+            .await
+            .unwrap_err()
+            .is::<reqwest::Error>());
+
+        server.other_rpc(
+            "get_transactions",
+            json!({
+                "credits": 0,
+                "top_hash": "",
+                "status": "OK",
+                "missed_tx": [],
+                "untrusted": false,
+            }),
+        );
+
+        let rsp = daemon_rpc
+            .get_transactions(vec![], Some(true), None)
+            .await
+            .unwrap();
+        assert_eq!(rsp.status, "OK");
+        assert_eq!(
+            server.requests()[1],
+            MockRequest {
+                method: "get_transactions".to_string(),
+                params: json!({ "txs_hashes": [], "decode_as_json": true }),
+            }
+        );
+    }
+}
@@ -12,18 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::util::*;
+use crate::{util::*, Secret};
 use chrono::prelude::*;
 use monero::{
+    consensus::{deserialize, encode::deserialize_partial},
     cryptonote::{hash::Hash as CryptoNoteHash, subaddress},
     util::{
-        address::PaymentId,
+        address::{AddressType, PaymentId},
         amount::{self, Amount},
     },
-    Address,
+    Address, Network, VarInt,
 };
 use serde::{Deserialize, Deserializer, Serialize};
-use std::{collections::HashMap, num::NonZeroU64};
+use std::{collections::HashMap, num::NonZeroU64, ops::Range, str::FromStr};
+
+/// Tag of the extra nonce field of the extra of a transaction.
+const TX_EXTRA_NONCE: u8 = 0x02;
 
 macro_rules! hash_type {
     ($name:ident, $len:expr) => {
@@ -63,23 +67,196 @@ pub struct BlockTemplate {
     #[serde(with = "amount::serde::as_pico")]
     pub expected_reward: Amount,
     pub height: u64,
+    /// RandomX key of the next seed epoch, once known.
+    #[serde(default, deserialize_with = "deserialize_empty_string_as_no_hash")]
+    pub next_seed_hash: Option<CryptoNoteHash>,
     pub prev_hash: HashString<BlockHash>,
     pub reserved_offset: u64,
+    /// RandomX key to hash the block with.
+    pub seed_hash: HashString<CryptoNoteHash>,
+    /// Height of the block whose hash is the RandomX key.
+    pub seed_height: u64,
     pub untrusted: bool,
+    /// Difficulty of the block, on 128 bits.
+    #[serde(with = "wide_hex")]
+    pub wide_difficulty: u128,
+}
+
+impl BlockTemplate {
+    /// Range of the bytes of `blocktemplate_blob` reserved for extra nonces, as requested with
+    /// `reserve_size`.
+    ///
+    /// `reserved_offset` points right after the tag and the size of the extra nonce field of the
+    /// coinbase transaction, assuming the size takes one byte: sizes over 127 are handled too.
+    fn reserved_space(&self) -> anyhow::Result<Range<usize>> {
+        let blob = &self.blocktemplate_blob.0;
+        let offset = self.reserved_offset as usize;
+        if offset < 2 || offset > blob.len() || blob[offset - 2] != TX_EXTRA_NONCE {
+            anyhow::bail!("No space reserved in the block template");
+        }
+
+        let (size, size_len) = deserialize_partial::<VarInt>(&blob[offset - 1..])?;
+        let start = offset - 1 + size_len;
+        let end = start + size.0 as usize;
+        if end > blob.len() {
+            anyhow::bail!("Reserved space past the end of the block template");
+        }
+        Ok(start..end)
+    }
+
+    /// Number of bytes reserved for extra nonces, 0 if none were.
+    pub fn reserved_size(&self) -> usize {
+        self.reserved_space().map_or(0, |space| space.len())
+    }
+
+    /// Write `extra_nonce` at the start of the reserved space of the coinbase transaction, e.g.
+    /// to give each miner of a pool its own search space, and update `blockhashing_blob`.
+    pub fn set_extra_nonce(&mut self, extra_nonce: &[u8]) -> anyhow::Result<()> {
+        let space = self.reserved_space()?;
+        if extra_nonce.len() > space.len() {
+            anyhow::bail!(
+                "Extra nonce of {} bytes larger than the {} reserved bytes",
+                extra_nonce.len(),
+                space.len()
+            );
+        }
+
+        self.blocktemplate_blob.0[space.start..space.start + extra_nonce.len()]
+            .copy_from_slice(extra_nonce);
+        self.blockhashing_blob = HashString(self.hashing_blob()?);
+        Ok(())
+    }
+
+    /// Set the nonce of the block, in both `blocktemplate_blob` and `blockhashing_blob`.
+    pub fn set_nonce(&mut self, nonce: u32) -> anyhow::Result<()> {
+        // the nonce ends the header, which starts both blobs
+        let (_, header_len) = deserialize_partial::<monero::blockdata::block::BlockHeader>(
+            &self.blocktemplate_blob.0,
+        )?;
+        let nonce_range = header_len - 4..header_len;
+
+        for blob in [
+            &mut self.blocktemplate_blob.0,
+            &mut self.blockhashing_blob.0,
+        ] {
+            if blob.len() < header_len {
+                anyhow::bail!("Block template shorter than its header");
+            }
+            blob[nonce_range.clone()].copy_from_slice(&nonce.to_le_bytes());
+        }
+        Ok(())
+    }
+
+    /// Compute the blob to hash for the proof of work from `blocktemplate_blob`: the header,
+    /// the merkle root of the transactions and their number.
+    pub fn hashing_blob(&self) -> anyhow::Result<Vec<u8>> {
+        let block = deserialize::<monero::Block>(&self.blocktemplate_blob.0)?;
+        Ok(block.serialize_hashable())
+    }
+
+    /// Block blob, as hex, to give to `submit_block` once mined.
+    pub fn submit_blob(&self) -> String {
+        self.blocktemplate_blob.to_string()
+    }
+}
+
+/// Argument type of daemon `get_block_template`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GetBlockTemplateArgs {
+    /// Address receiving the reward of the block.
+    pub wallet_address: Address,
+    /// Number of bytes to reserve in the coinbase transaction, for the extra nonces of a pool.
+    pub reserve_size: Option<u64>,
+    /// Extra nonce to put in the coinbase transaction, instead of reserving space.
+    pub extra_nonce: Option<Vec<u8>>,
+    /// Block to mine on, instead of the tip of the chain.
+    pub prev_block: Option<BlockHash>,
+}
+
+/// Return sub-type of daemon `get_miner_data`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TxBacklogEntry {
+    /// Hash of the transaction.
+    pub id: HashString<CryptoNoteHash>,
+    /// Weight of the transaction.
+    pub weight: u64,
+    /// Fee paid by the transaction.
+    #[serde(with = "amount::serde::as_pico")]
+    pub fee: Amount,
+}
+
+/// Return type of daemon `get_miner_data`, everything needed to build a block template without
+/// the node.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MinerData {
+    /// Major version of the next block.
+    pub major_version: u8,
+    /// Height of the next block.
+    pub height: u64,
+    /// Hash of the block to mine on.
+    pub prev_id: HashString<BlockHash>,
+    /// RandomX key to hash the next block with.
+    pub seed_hash: HashString<CryptoNoteHash>,
+    /// Difficulty of the next block.
+    #[serde(with = "wide_hex")]
+    pub difficulty: u128,
+    /// Median weight of the last blocks, used to compute the penalty of larger blocks.
+    pub median_weight: u64,
+    /// Coins emitted so far.
+    #[serde(with = "amount::serde::as_pico")]
+    pub already_generated_coins: Amount,
+    /// Transactions waiting in the pool.
+    #[serde(default)]
+    pub tx_backlog: Vec<TxBacklogEntry>,
+}
+
+/// Merge mined chain, argument and return sub-type of daemon `add_aux_pow`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AuxPow {
+    /// Identifier of the chain.
+    pub id: HashString<CryptoNoteHash>,
+    /// Hash of the block of the chain.
+    pub hash: HashString<CryptoNoteHash>,
+}
+
+/// Return type of daemon `add_aux_pow`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AuxPowTemplate {
+    /// Block template committing to the merge mined chains.
+    pub blocktemplate_blob: HashString<Vec<u8>>,
+    /// Blob to hash when mining the block template.
+    pub blockhashing_blob: HashString<Vec<u8>>,
+    /// Root of the merkle tree of the merge mined chains.
+    pub merkle_root: HashString<CryptoNoteHash>,
+    /// Depth of the merkle tree of the merge mined chains.
+    pub merkle_tree_depth: u64,
+    /// Merge mined chains, in the order of the merkle tree.
+    pub aux_pow: Vec<AuxPow>,
 }
 
 #[derive(Deserialize)]
 pub(crate) struct BlockHeaderResponseR {
     pub block_size: u64,
+    pub block_weight: u64,
+    pub cumulative_difficulty: u64,
+    #[serde(default)]
+    pub cumulative_difficulty_top64: u64,
     pub depth: u64,
     pub difficulty: u64,
+    #[serde(default)]
+    pub difficulty_top64: u64,
     pub hash: HashString<BlockHash>,
     pub height: u64,
+    #[serde(default)]
+    pub long_term_weight: u64,
     pub major_version: u64,
+    pub miner_tx_hash: HashString<CryptoNoteHash>,
     pub minor_version: u64,
     pub nonce: u32,
     pub num_txes: u64,
     pub orphan_status: bool,
+    #[serde(default, deserialize_with = "deserialize_empty_string_as_no_hash")]
+    pub pow_hash: Option<CryptoNoteHash>,
     pub prev_hash: HashString<BlockHash>,
     #[serde(with = "amount::serde::as_pico")]
     pub reward: Amount,
@@ -87,22 +264,57 @@ pub(crate) struct BlockHeaderResponseR {
     pub timestamp: DateTime<Utc>,
 }
 
+/// (De)serialization of 128-bit values sent as hex strings, e.g. `0x1bc16d674ec80000`.
+mod wide_hex {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &u128, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{:#x}", value))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u128, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        u128::from_str_radix(s.strip_prefix("0x").unwrap_or(&s), 16)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Combine the low and high 64 bits of a 128-bit difficulty.
+fn wide(low: u64, top64: u64) -> u128 {
+    (u128::from(top64) << 64) | u128::from(low)
+}
+
 impl From<BlockHeaderResponseR> for BlockHeaderResponse {
     fn from(value: BlockHeaderResponseR) -> Self {
         Self {
             block_size: value.block_size,
+            block_weight: value.block_weight,
+            cumulative_difficulty: wide(
+                value.cumulative_difficulty,
+                value.cumulative_difficulty_top64,
+            ),
             depth: value.depth,
             difficulty: value.difficulty,
             hash: value.hash.0,
             height: value.height,
+            long_term_weight: value.long_term_weight,
             major_version: value.major_version,
+            miner_tx_hash: value.miner_tx_hash.0,
             minor_version: value.minor_version,
             nonce: value.nonce,
             num_txes: value.num_txes,
             orphan_status: value.orphan_status,
+            pow_hash: value.pow_hash,
             prev_hash: value.prev_hash.0,
             reward: value.reward,
             timestamp: value.timestamp,
+            wide_difficulty: wide(value.difficulty, value.difficulty_top64),
         }
     }
 }
@@ -111,19 +323,32 @@ impl From<BlockHeaderResponseR> for BlockHeaderResponse {
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BlockHeaderResponse {
     pub block_size: u64,
+    /// Weight of the block, used for the block size limit and fees.
+    pub block_weight: u64,
+    /// Cumulative difficulty of the chain up to this block.
+    pub cumulative_difficulty: u128,
     pub depth: u64,
+    /// Low 64 bits of the difficulty, see [`Self::wide_difficulty`].
     pub difficulty: u64,
     pub hash: BlockHash,
     pub height: u64,
+    /// Long term weight of the block, used for the median of the block weights.
+    pub long_term_weight: u64,
     pub major_version: u64,
+    /// Hash of the coinbase transaction of the block.
+    pub miner_tx_hash: CryptoNoteHash,
     pub minor_version: u64,
     pub nonce: u32,
     pub num_txes: u64,
     pub orphan_status: bool,
+    /// Proof-of-work hash of the block, only given by nodes asked to compute it.
+    pub pow_hash: Option<CryptoNoteHash>,
     pub prev_hash: BlockHash,
     #[serde(with = "amount::serde::as_pico")]
     pub reward: Amount,
     pub timestamp: DateTime<Utc>,
+    /// Difficulty of the block, on 128 bits.
+    pub wide_difficulty: u128,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -185,6 +410,545 @@ pub struct JsonTransaction {
     // vin, vout, extra, rct_signatures, rct_sig_prunable
 }
 
+/// State of a [`Connection`], as reported by daemon `get_connections`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// The handshake with the peer is not done yet.
+    BeforeHandshake,
+    /// Blocks are being downloaded from the peer.
+    Synchronizing,
+    /// Waiting for blocks requested from other peers.
+    Standby,
+    /// Nothing to download from the peer.
+    Idle,
+    /// Synchronized with the peer.
+    Normal,
+    /// Any state unknown to this crate.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Return sub-type of daemon `get_connections`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Connection {
+    /// Address of the peer, with its port.
+    pub address: String,
+    /// Type of address: 1 for IPv4, 2 for IPv6, 3 for Tor and 4 for I2P.
+    #[serde(default)]
+    pub address_type: u8,
+    /// Average download speed, in kB/s.
+    pub avg_download: u64,
+    /// Average upload speed, in kB/s.
+    pub avg_upload: u64,
+    /// Id of the connection.
+    pub connection_id: String,
+    /// Current download speed, in kB/s.
+    pub current_download: u64,
+    /// Current upload speed, in kB/s.
+    pub current_upload: u64,
+    /// Height of the peer's chain.
+    pub height: u64,
+    /// Host of the peer.
+    pub host: String,
+    /// True if the peer opened the connection.
+    pub incoming: bool,
+    /// IP address of the peer.
+    pub ip: String,
+    /// Number of seconds since the connection was opened.
+    pub live_time: u64,
+    /// True if the peer is on the local network.
+    pub local_ip: bool,
+    /// True if the peer is on the same host.
+    pub localhost: bool,
+    /// Id of the peer in the network.
+    pub peer_id: String,
+    /// Port of the peer.
+    pub port: String,
+    /// Pruning seed of the peer, 0 if it is not pruned.
+    #[serde(default)]
+    pub pruning_seed: u32,
+    /// Number of bytes received from the peer.
+    pub recv_count: u64,
+    /// Number of seconds since the last data received from the peer.
+    pub recv_idle_time: u64,
+    /// Port of the RPC server of the peer, 0 if it does not advertise one.
+    #[serde(default)]
+    pub rpc_port: u16,
+    /// Number of bytes sent to the peer.
+    pub send_count: u64,
+    /// Number of seconds since the last data sent to the peer.
+    pub send_idle_time: u64,
+    /// State of the connection.
+    pub state: ConnectionState,
+    /// Flags of the features supported by the peer.
+    pub support_flags: u32,
+}
+
+/// Return sub-type of daemon RPC `get_peer_list`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Peer {
+    /// Id of the peer in the network.
+    pub id: u64,
+    /// Host of the peer.
+    pub host: String,
+    /// IPv4 address of the peer as an integer, 0 for other types of address.
+    #[serde(default)]
+    pub ip: u32,
+    /// Port of the peer.
+    pub port: u16,
+    /// Port of the RPC server of the peer, 0 if it does not advertise one.
+    #[serde(default)]
+    pub rpc_port: u16,
+    /// Unix time at which the peer was last seen.
+    #[serde(default)]
+    pub last_seen: u64,
+    /// Pruning seed of the peer, 0 if it is not pruned.
+    #[serde(default)]
+    pub pruning_seed: u32,
+}
+
+/// Return type of daemon RPC `get_peer_list`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PeerList {
+    /// Peers the node was recently connected to.
+    #[serde(default)]
+    pub white_list: Vec<Peer>,
+    /// Peers the node heard of, but was not connected to.
+    #[serde(default)]
+    pub gray_list: Vec<Peer>,
+}
+
+/// Return sub-type of daemon `get_bans`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Ban {
+    /// Banned host or subnet.
+    pub host: String,
+    /// Banned IPv4 address as an integer, 0 for other types of address.
+    #[serde(default)]
+    pub ip: u32,
+    /// Number of seconds left until the ban expires.
+    pub seconds: u32,
+}
+
+/// Argument type of daemon `set_bans`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SetBanArgs {
+    /// Host or subnet to ban or unban, e.g. `1.2.3.4` or `1.2.3.0/24`.
+    pub host: String,
+    /// True to ban the host, false to lift its ban.
+    pub ban: bool,
+    /// Duration of the ban, in seconds.
+    pub seconds: u32,
+}
+
+/// Return type of daemon RPC `get_limit` and `set_limit`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BandwidthLimits {
+    /// Download limit, in kB/s.
+    pub limit_down: u64,
+    /// Upload limit, in kB/s.
+    pub limit_up: u64,
+}
+
+/// Return type of daemon RPC `get_net_stats`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NetStats {
+    /// Unix time at which the node started.
+    pub start_time: u64,
+    /// Number of packets received.
+    pub total_packets_in: u64,
+    /// Number of bytes received.
+    pub total_bytes_in: u64,
+    /// Number of packets sent.
+    pub total_packets_out: u64,
+    /// Number of bytes sent.
+    pub total_bytes_out: u64,
+}
+
+/// Return sub-type of daemon `sync_info`: a span of blocks being downloaded from a peer.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SyncSpan {
+    /// Id of the connection the blocks are downloaded from.
+    pub connection_id: String,
+    /// Number of blocks in the span.
+    pub nblocks: u64,
+    /// Download rate, in bytes per second.
+    pub rate: u32,
+    /// Address of the peer the blocks are downloaded from.
+    pub remote_address: String,
+    /// Size of the span, in bytes.
+    pub size: u64,
+    /// Download speed, as a percentage of the speed of the fastest peer.
+    pub speed: u32,
+    /// Height of the first block of the span.
+    pub start_block_height: u64,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SyncPeerR {
+    pub info: Connection,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SyncInfoR {
+    pub height: u64,
+    #[serde(default)]
+    pub target_height: u64,
+    #[serde(default)]
+    pub next_needed_pruning_seed: u32,
+    #[serde(default)]
+    pub overview: String,
+    #[serde(default)]
+    pub peers: Vec<SyncPeerR>,
+    #[serde(default)]
+    pub spans: Vec<SyncSpan>,
+}
+
+impl From<SyncInfoR> for SyncInfo {
+    fn from(value: SyncInfoR) -> Self {
+        Self {
+            height: value.height,
+            target_height: value.target_height,
+            next_needed_pruning_seed: value.next_needed_pruning_seed,
+            overview: value.overview,
+            peers: value.peers.into_iter().map(|peer| peer.info).collect(),
+            spans: value.spans,
+        }
+    }
+}
+
+/// Return type of daemon `sync_info`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SyncInfo {
+    /// Number of blocks in the chain of the node.
+    pub height: u64,
+    /// Height the node is synchronizing to, 0 if it is not synchronizing.
+    pub target_height: u64,
+    /// Pruning seed of the blocks the node needs next, 0 if it is not pruned.
+    pub next_needed_pruning_seed: u32,
+    /// Overview of the block queue, as printed by the daemon.
+    pub overview: String,
+    /// Peers the node is connected to.
+    pub peers: Vec<Connection>,
+    /// Spans of blocks being downloaded.
+    pub spans: Vec<SyncSpan>,
+}
+
+impl SyncInfo {
+    /// True if the node has caught up with its peers.
+    ///
+    /// The node only sets a target height when a peer advertises blocks it does not have, so a
+    /// target height of 0, e.g. for a node already at the tip of its peers, means synchronized.
+    pub fn is_synced(&self) -> bool {
+        self.height >= self.target_height
+    }
+}
+
+/// State of a hard fork, part of [`HardForkInfo`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum HardForkState {
+    /// The fork likely happened without the node: it needs to be updated.
+    LikelyForked,
+    /// The fork is scheduled and the node needs to be updated before it happens.
+    UpdateNeeded,
+    /// The node is ready for the next fork.
+    Ready,
+}
+
+/// Return type of daemon `hard_fork_info`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct HardForkInfo {
+    /// Height at which the queried version takes effect.
+    pub earliest_height: u64,
+    /// True if the queried version is enabled.
+    pub enabled: bool,
+    /// State of the node regarding the next fork.
+    pub state: HardForkState,
+    /// Number of votes needed to enable the queried version.
+    pub threshold: u32,
+    /// Version the node is running, or the queried version.
+    pub version: u8,
+    /// Number of blocks of the window voting for the queried version.
+    pub votes: u32,
+    /// Version the latest blocks are voting for.
+    pub voting: u8,
+    /// Number of blocks over which votes are counted.
+    pub window: u32,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct CoinbaseTxSumR {
+    pub emission_amount: u64,
+    #[serde(default)]
+    pub emission_amount_top64: u64,
+    pub fee_amount: u64,
+    #[serde(default)]
+    pub fee_amount_top64: u64,
+}
+
+impl From<CoinbaseTxSumR> for CoinbaseTxSum {
+    fn from(value: CoinbaseTxSumR) -> Self {
+        Self {
+            emission_amount: Amount::from_pico(value.emission_amount),
+            fee_amount: Amount::from_pico(value.fee_amount),
+            wide_emission_amount: wide(value.emission_amount, value.emission_amount_top64),
+            wide_fee_amount: wide(value.fee_amount, value.fee_amount_top64),
+        }
+    }
+}
+
+/// Return type of daemon `get_coinbase_tx_sum`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CoinbaseTxSum {
+    /// Coins emitted over the blocks, truncated to 64 bits.
+    pub emission_amount: Amount,
+    /// Fees paid over the blocks, truncated to 64 bits.
+    pub fee_amount: Amount,
+    /// Coins emitted over the blocks, in piconero.
+    pub wide_emission_amount: u128,
+    /// Fees paid over the blocks, in piconero.
+    pub wide_fee_amount: u128,
+}
+
+/// Argument type of daemon `get_output_histogram`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OutputHistogramArgs {
+    /// Amounts to count the outputs of, all amounts if empty.
+    pub amounts: Vec<Amount>,
+    /// Only return amounts with at least this many outputs.
+    pub min_count: Option<u64>,
+    /// Only return amounts with at most this many outputs.
+    pub max_count: Option<u64>,
+    /// Only count unlocked outputs.
+    pub unlocked: Option<bool>,
+    /// Unix time after which outputs are counted as recent.
+    pub recent_cutoff: Option<u64>,
+}
+
+/// Return sub-type of daemon `get_output_histogram`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OutputHistogramEntry {
+    /// Amount of the outputs, zero for RingCT outputs.
+    #[serde(with = "amount::serde::as_pico")]
+    pub amount: Amount,
+    /// Number of outputs of the amount.
+    pub total_instances: u64,
+    /// Number of unlocked outputs of the amount.
+    pub unlocked_instances: u64,
+    /// Number of outputs of the amount created after the recent cutoff.
+    pub recent_instances: u64,
+}
+
+/// Argument type of daemon `get_output_distribution`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OutputDistributionArgs {
+    /// Amounts to look up, zero for RingCT outputs.
+    pub amounts: Vec<Amount>,
+    /// First height of the distribution, 0 if not set.
+    pub from_height: Option<u64>,
+    /// Last height of the distribution, the tip if not set.
+    pub to_height: Option<u64>,
+    /// Return the number of outputs created up to each block instead of in each block.
+    pub cumulative: bool,
+    /// Fetch the distribution as a blob through the binary endpoint
+    /// `get_output_distribution.bin` instead of a list of numbers over JSON-RPC.
+    pub binary: bool,
+    /// Have the node pack the blob with varints, only used with `binary`.
+    pub compress: bool,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct OutputDistributionR {
+    pub amount: u64,
+    pub base: u64,
+    pub distribution: Vec<u64>,
+    pub start_height: u64,
+}
+
+impl From<OutputDistributionR> for OutputDistribution {
+    fn from(value: OutputDistributionR) -> Self {
+        Self {
+            amount: Amount::from_pico(value.amount),
+            base: value.base,
+            distribution: value.distribution,
+            start_height: value.start_height,
+        }
+    }
+}
+
+/// Decode a blob of varints, as packed by the node.
+fn decode_varints(bytes: &[u8]) -> anyhow::Result<Vec<u64>> {
+    let mut values = Vec::new();
+    let mut value = 0u64;
+    let mut shift = 0;
+
+    for byte in bytes {
+        if shift >= 64 {
+            anyhow::bail!("Varint overflows 64 bits");
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            values.push(value);
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 7;
+        }
+    }
+    if shift != 0 {
+        anyhow::bail!("Truncated varint");
+    }
+
+    Ok(values)
+}
+
+/// Decode a distribution sent by `get_output_distribution.bin`, either packed with varints
+/// (`compressed_data`) or as little endian 64 bits integers (`distribution`).
+pub(crate) fn decode_distribution_blob(blob: &[u8], compressed: bool) -> anyhow::Result<Vec<u64>> {
+    if compressed {
+        return decode_varints(blob);
+    }
+    if blob.len() % 8 != 0 {
+        anyhow::bail!("Invalid distribution blob of {} bytes", blob.len());
+    }
+    Ok(blob
+        .chunks(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect())
+}
+
+/// Return sub-type of daemon `get_output_distribution`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OutputDistribution {
+    /// Amount of the outputs, zero for RingCT outputs.
+    #[serde(with = "amount::serde::as_pico")]
+    pub amount: Amount,
+    /// Number of outputs created before `start_height`, included in the first value of
+    /// cumulative distributions.
+    pub base: u64,
+    /// Number of outputs created in, or up to, each block from `start_height`.
+    pub distribution: Vec<u64>,
+    /// Height of the first block of the distribution.
+    pub start_height: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct OutKeyR {
+    pub key: HashString<monero::PublicKey>,
+    pub mask: HashString<monero::util::ringct::Key>,
+    pub unlocked: bool,
+    pub height: u64,
+    pub txid: HashString<CryptoNoteHash>,
+}
+
+impl From<OutKeyR> for OutKey {
+    fn from(value: OutKeyR) -> Self {
+        Self {
+            key: value.key.0,
+            mask: value.mask.0,
+            unlocked: value.unlocked,
+            height: value.height,
+            txid: value.txid.0,
+        }
+    }
+}
+
+/// Return sub-type of daemon RPC `get_outs`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OutKey {
+    /// One-time public key of the output.
+    pub key: monero::PublicKey,
+    /// Commitment to the amount of the output.
+    pub mask: monero::util::ringct::Key,
+    /// True if the output can be spent.
+    pub unlocked: bool,
+    /// Height of the block holding the transaction of the output.
+    pub height: u64,
+    /// Hash of the transaction of the output.
+    pub txid: CryptoNoteHash,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MiningStatusR {
+    pub active: bool,
+    #[serde(default, deserialize_with = "deserialize_empty_string_as_no_address")]
+    pub address: Option<Address>,
+    #[serde(default)]
+    pub bg_idle_threshold: u8,
+    #[serde(default)]
+    pub bg_ignore_battery: bool,
+    #[serde(default)]
+    pub bg_min_idle_seconds: u8,
+    #[serde(default)]
+    pub bg_target: u8,
+    #[serde(default, with = "amount::serde::as_pico")]
+    pub block_reward: Amount,
+    pub block_target: u32,
+    #[serde(default)]
+    pub difficulty: u64,
+    #[serde(default)]
+    pub difficulty_top64: u64,
+    #[serde(default)]
+    pub is_background_mining_enabled: bool,
+    #[serde(default)]
+    pub pow_algorithm: String,
+    pub speed: u64,
+    pub threads_count: u32,
+}
+
+impl From<MiningStatusR> for MiningStatus {
+    fn from(value: MiningStatusR) -> Self {
+        Self {
+            active: value.active,
+            address: value.address,
+            bg_idle_threshold: value.bg_idle_threshold,
+            bg_ignore_battery: value.bg_ignore_battery,
+            bg_min_idle_seconds: value.bg_min_idle_seconds,
+            bg_target: value.bg_target,
+            block_reward: value.block_reward,
+            block_target: value.block_target,
+            difficulty: wide(value.difficulty, value.difficulty_top64),
+            is_background_mining_enabled: value.is_background_mining_enabled,
+            pow_algorithm: value.pow_algorithm,
+            speed: value.speed,
+            threads_count: value.threads_count,
+        }
+    }
+}
+
+/// Return type of daemon RPC `mining_status`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MiningStatus {
+    /// True if the node is mining.
+    pub active: bool,
+    /// Address the node mines to, if it is mining.
+    pub address: Option<Address>,
+    /// CPU usage, in percent, under which background mining runs.
+    pub bg_idle_threshold: u8,
+    /// True if background mining also runs on battery.
+    pub bg_ignore_battery: bool,
+    /// Number of idle seconds after which background mining starts.
+    pub bg_min_idle_seconds: u8,
+    /// CPU usage, in percent, of background mining.
+    pub bg_target: u8,
+    /// Reward of the next block.
+    #[serde(with = "amount::serde::as_pico")]
+    pub block_reward: Amount,
+    /// Target time between blocks, in seconds.
+    pub block_target: u32,
+    /// Difficulty of the next block.
+    pub difficulty: u128,
+    /// True if background mining is enabled.
+    pub is_background_mining_enabled: bool,
+    /// Proof-of-work algorithm, e.g. `RandomX`.
+    pub pow_algorithm: String,
+    /// Hash rate, in hashes per second.
+    pub speed: u64,
+    /// Number of mining threads.
+    pub threads_count: u32,
+}
+
 /// Sub-type of [`BalanceData`]'s return type of wallet `get_balance`.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct SubaddressBalanceData {
@@ -232,9 +996,19 @@ pub struct TransferData {
     pub fee: Amount,
     pub tx_blob: HashString<Vec<u8>>,
     pub tx_hash: HashString<CryptoNoteHash>,
-    pub tx_key: HashString<Vec<u8>>,
+    pub tx_key: Secret<HashString<Vec<u8>>>,
     pub tx_metadata: HashString<Vec<u8>>,
     pub unsigned_txset: HashString<Vec<u8>>,
+    pub weight: u64,
+}
+
+/// Return type of wallet `estimate_tx_size_and_weight`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TxSizeAndWeight {
+    /// Estimated size of the transaction in bytes.
+    pub size: u64,
+    /// Estimated weight of the transaction.
+    pub weight: u64,
 }
 
 /// Sub-type of [`AddressData`]'s return type of wallet `get_address`.
@@ -268,6 +1042,96 @@ pub struct WalletCreation {
     pub info: String,
 }
 
+/// Return type of wallet `validate_address` and [`AddressValidation::offline`].
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+pub struct AddressValidation {
+    /// True if the input is a valid address.
+    pub valid: bool,
+    /// True if the given address is an integrated address.
+    pub integrated: bool,
+    /// True if the given address is a subaddress.
+    pub subaddress: bool,
+    /// Network type of the address, if valid.
+    #[serde(deserialize_with = "deserialize_nettype")]
+    pub nettype: Option<Network>,
+    /// Address resolved through OpenAlias, if any.
+    #[serde(deserialize_with = "deserialize_empty_string_as_none")]
+    pub openalias_address: Option<String>,
+}
+
+impl AddressValidation {
+    /// Validate an address locally, without calling the wallet RPC. Pass `None` as `network`
+    /// to accept an address from any network, like `any_net_type` in wallet `validate_address`.
+    /// OpenAlias addresses are never resolved.
+    pub fn offline(address: &str, network: Option<Network>) -> Self {
+        match Address::from_str(address) {
+            Ok(address) if network.map_or(true, |net| net == address.network) => Self {
+                valid: true,
+                integrated: matches!(address.addr_type, AddressType::Integrated(_)),
+                subaddress: address.addr_type == AddressType::SubAddress,
+                nettype: Some(address.network),
+                openalias_address: None,
+            },
+            _ => Self {
+                valid: false,
+                integrated: false,
+                subaddress: false,
+                nettype: None,
+                openalias_address: None,
+            },
+        }
+    }
+}
+
+fn deserialize_nettype<'de, D>(deserializer: D) -> Result<Option<Network>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let v = String::deserialize(deserializer)?;
+    Ok(match v.as_str() {
+        "" => None,
+        "mainnet" => Some(Network::Mainnet),
+        "testnet" => Some(Network::Testnet),
+        "stagenet" => Some(Network::Stagenet),
+        other => {
+            return Err(serde::de::Error::custom(format!(
+                "Invalid network type {}, expected mainnet, testnet or stagenet",
+                other
+            )))
+        }
+    })
+}
+
+fn deserialize_empty_string_as_none<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let v = String::deserialize(deserializer)?;
+    Ok(if v.is_empty() { None } else { Some(v) })
+}
+
+fn deserialize_empty_string_as_no_hash<'de, D>(
+    deserializer: D,
+) -> Result<Option<CryptoNoteHash>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_empty_string_as_none(deserializer)?
+        .map(|v| <CryptoNoteHash as HashType>::from_str(&v).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn deserialize_empty_string_as_no_address<'de, D>(
+    deserializer: D,
+) -> Result<Option<Address>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_empty_string_as_none(deserializer)?
+        .map(|v| Address::from_str(&v).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
 /// Return type of wallet `get_address`.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct AddressData {
@@ -303,6 +1167,17 @@ pub struct IncomingTransfer {
     pub tx_hash: HashString<CryptoNoteHash>,
     pub tx_size: Option<u64>,
     pub block_height: Option<u64>,
+    /// True if the output is frozen and will not be used in transfers. False if the wallet is
+    /// too old to report it.
+    #[serde(default)]
+    pub frozen: bool,
+    /// True if the output is unlocked and can be spent. False if the wallet is too old to report
+    /// it.
+    #[serde(default)]
+    pub unlocked: bool,
+    /// Public key of the output, empty if the wallet is too old to report it.
+    #[serde(default)]
+    pub pubkey: String,
 }
 
 /// Argument type of wallet `sweep_all`.
@@ -327,7 +1202,7 @@ pub struct SweepAllArgs {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SweepAllData {
     pub tx_hash_list: Vec<HashString<CryptoNoteHash>>,
-    pub tx_key_list: Option<Vec<HashString<CryptoNoteHash>>>,
+    pub tx_key_list: Option<Vec<Secret<HashString<CryptoNoteHash>>>>,
     #[serde(
         default,
         serialize_with = "amount::serde::as_pico::slice::serialize",
@@ -359,17 +1234,48 @@ pub struct TransferOptions {
     pub do_not_relay: Option<bool>,
 }
 
+/// Sub-type of [`SetDaemonOptions`]. Select whether SSL is used to connect to the daemon.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SslSupport {
+    Autodetect,
+    Enabled,
+    Disabled,
+}
+
+/// Argument type of wallet `set_daemon`.
+#[derive(Clone, Debug, Default)]
+pub struct SetDaemonOptions {
+    /// If true, the daemon is trusted by the wallet.
+    pub trusted: Option<bool>,
+    /// Whether to use SSL, defaults to autodetect.
+    pub ssl_support: Option<SslSupport>,
+    /// Path to the SSL private key.
+    pub ssl_private_key_path: Option<String>,
+    /// Path to the SSL certificate.
+    pub ssl_certificate_path: Option<String>,
+    /// Path to the CA file used to verify the daemon certificate.
+    pub ssl_ca_file: Option<String>,
+    /// List of allowed certificate fingerprints.
+    pub ssl_allowed_fingerprints: Option<Vec<String>>,
+    /// If true, accept any certificate presented by the daemon.
+    pub ssl_allow_any_cert: Option<bool>,
+    /// Username used to authenticate with the daemon.
+    pub username: Option<String>,
+    /// Password used to authenticate with the daemon.
+    pub password: Option<Secret<String>>,
+}
+
 /// Argument type of wallet `generate_from_keys`.
 #[derive(Clone, Debug)]
 pub struct GenerateFromKeysArgs {
     pub restore_height: Option<u64>,
     pub filename: String,
     pub address: Address,
-    pub spendkey: Option<monero::PrivateKey>,
-    pub viewkey: monero::PrivateKey,
+    pub spendkey: Option<Secret<monero::PrivateKey>>,
+    pub viewkey: Secret<monero::PrivateKey>,
     // TODO it seems this argument is really optional, although the doc at
     // `https://www.getmonero.org/resources/developer-guides/wallet-rpc.html#generate_from_keys` does not mention it
-    pub password: String,
+    pub password: Secret<String>,
     pub autosave_current: Option<bool>,
 }
 
@@ -378,10 +1284,10 @@ pub struct GenerateFromKeysArgs {
 pub struct RestoreDeterministicWalletArgs {
     pub autosave_current: Option<bool>,
     pub filename: String,
-    pub password: String,
+    pub password: Secret<String>,
     pub restore_height: Option<u64>,
-    pub seed: String,
-    pub seed_offset: Option<String>,
+    pub seed: Secret<String>,
+    pub seed_offset: Option<Secret<String>>,
 }
 
 /// Return type of wallet `restore_deterministic_wallet`.
@@ -392,7 +1298,7 @@ pub struct WalletRestoration {
     /// Info on generated wallet.
     pub info: String,
     /// The mnemonic seed phrase.
-    pub seed: String,
+    pub seed: Secret<String>,
     /// Indicates whether the seed provided to `restore_deterministic_wallet` has
     /// been updated from a deprecated format.
     pub was_deprecated: bool,
@@ -630,15 +1536,22 @@ mod tests {
     fn block_header_response_from_block_header_response_r() {
         let bhrr = BlockHeaderResponseR {
             block_size: 123,
+            block_weight: 124,
+            cumulative_difficulty: 3,
+            cumulative_difficulty_top64: 2,
             depth: 1234,
             difficulty: 12345,
+            difficulty_top64: 1,
             hash: HashString(BlockHash::zero()),
             height: 123456,
+            long_term_weight: 125,
             major_version: 1234567,
+            miner_tx_hash: HashString(CryptoNoteHash::repeat_byte(13)),
             minor_version: 12345678,
             nonce: 123456789,
             num_txes: 1,
             orphan_status: true,
+            pow_hash: None,
             prev_hash: HashString(BlockHash::repeat_byte(12)),
             reward: Amount::from_pico(12),
             timestamp: DateTime::from_timestamp(61, 0).unwrap(),
@@ -646,18 +1559,24 @@ mod tests {
 
         let expected_bhr = BlockHeaderResponse {
             block_size: 123,
+            block_weight: 124,
+            cumulative_difficulty: (2 << 64) + 3,
             depth: 1234,
             difficulty: 12345,
             hash: BlockHash::zero(),
             height: 123456,
+            long_term_weight: 125,
             major_version: 1234567,
+            miner_tx_hash: CryptoNoteHash::repeat_byte(13),
             minor_version: 12345678,
             nonce: 123456789,
             num_txes: 1,
             orphan_status: true,
+            pow_hash: None,
             prev_hash: BlockHash::repeat_byte(12),
             reward: Amount::from_pico(12),
             timestamp: DateTime::from_timestamp(61, 0).unwrap(),
+            wide_difficulty: (1 << 64) + 12345,
         };
 
         assert_eq!(BlockHeaderResponse::from(bhrr), expected_bhr);
@@ -693,6 +1612,90 @@ mod tests {
         assert_de_tokens(&in_pool, &[Token::U64(0)]);
     }
 
+    #[test]
+    fn address_validation_offline() {
+        use monero::{KeyPair, PrivateKey, ViewPair};
+
+        let key_pair = KeyPair {
+            view: PrivateKey::from_str(
+                "8ae33e57aee12fa4ad5b42a3ab093d9f3cb7f9be68b112a85f83275bcc5a190b",
+            )
+            .unwrap(),
+            spend: PrivateKey::from_str(
+                "eae5d41a112e14dcd549780a982bb3653c2f86ab1f4e6aa2b13c41f8b893ab04",
+            )
+            .unwrap(),
+        };
+        let standard = Address::from_keypair(Network::Stagenet, &key_pair);
+        let integrated = Address::integrated(
+            Network::Mainnet,
+            standard.public_spend,
+            standard.public_view,
+            PaymentId([1; 8]),
+        );
+        let subaddress = subaddress::get_subaddress(
+            &ViewPair::from(&key_pair),
+            subaddress::Index { major: 0, minor: 1 },
+            Some(Network::Testnet),
+        );
+
+        let validation = AddressValidation::offline(&standard.to_string(), None);
+        assert!(validation.valid);
+        assert!(!validation.integrated);
+        assert!(!validation.subaddress);
+        assert_eq!(validation.nettype, Some(Network::Stagenet));
+
+        let validation =
+            AddressValidation::offline(&integrated.to_string(), Some(Network::Mainnet));
+        assert!(validation.valid);
+        assert!(validation.integrated);
+        assert!(!validation.subaddress);
+        assert_eq!(validation.nettype, Some(Network::Mainnet));
+
+        let validation = AddressValidation::offline(&subaddress.to_string(), None);
+        assert!(validation.valid);
+        assert!(!validation.integrated);
+        assert!(validation.subaddress);
+        assert_eq!(validation.nettype, Some(Network::Testnet));
+
+        let invalid = AddressValidation {
+            valid: false,
+            integrated: false,
+            subaddress: false,
+            nettype: None,
+            openalias_address: None,
+        };
+        assert_eq!(
+            AddressValidation::offline(&standard.to_string(), Some(Network::Mainnet)),
+            invalid
+        );
+        assert_eq!(AddressValidation::offline("not an address", None), invalid);
+    }
+
+    #[test]
+    fn deserialize_for_address_validation() {
+        let validation: AddressValidation = serde_json::from_value(serde_json::json!({
+            "valid": false,
+            "integrated": false,
+            "subaddress": false,
+            "nettype": "",
+            "openalias_address": "",
+        }))
+        .unwrap();
+        assert_eq!(validation.nettype, None);
+        assert_eq!(validation.openalias_address, None);
+
+        let validation: AddressValidation = serde_json::from_value(serde_json::json!({
+            "valid": true,
+            "integrated": false,
+            "subaddress": true,
+            "nettype": "stagenet",
+            "openalias_address": "",
+        }))
+        .unwrap();
+        assert_eq!(validation.nettype, Some(Network::Stagenet));
+    }
+
     #[test]
     fn generate_blocks_response_from_generate_blocks_response_r() {
         let gbrr = GenerateBlocksResponseR {
@@ -717,4 +1720,243 @@ mod tests {
         };
         assert_eq!(GenerateBlocksResponse::from(gbrr), expected_gbr);
     }
+
+    #[test]
+    fn deserialize_block_header_response_r() {
+        let header: BlockHeaderResponseR = serde_json::from_value(serde_json::json!({
+            "block_size": 5500,
+            "block_weight": 5500,
+            "cumulative_difficulty": 86164894009456483u64,
+            "cumulative_difficulty_top64": 0,
+            "depth": 0,
+            "difficulty": 227026389695u64,
+            "difficulty_top64": 0,
+            "hash": "a6ad87cf357a1aac1ee1d7cb0afa4c2e653b0b1ab7d5bf6af310333e43c59dd0",
+            "height": 2286454,
+            "long_term_weight": 5500,
+            "major_version": 14,
+            "miner_tx_hash": "a474f87de1645ff14c5e90c477b07f9bc86a22fb42909caa0705239298da96d0",
+            "minor_version": 14,
+            "nonce": 249602367,
+            "num_txes": 3,
+            "orphan_status": false,
+            "pow_hash": "",
+            "prev_hash": "fa17fefe1d05da775a61a3dc33d9e199d12af167ef0ab37e52b51e8487b50f25",
+            "reward": 1181337498013u64,
+            "timestamp": 1612088597,
+            "wide_cumulative_difficulty": "0x1321e83bb8af763",
+            "wide_difficulty": "0x34dbd3cabf"
+        }))
+        .unwrap();
+
+        let header = BlockHeaderResponse::from(header);
+        assert_eq!(header.cumulative_difficulty, 0x1321e83bb8af763);
+        assert_eq!(header.wide_difficulty, 0x34dbd3cabf);
+        assert_eq!(header.long_term_weight, 5500);
+        assert_eq!(header.pow_hash, None);
+        assert_eq!(
+            header.miner_tx_hash,
+            <CryptoNoteHash as FromStr>::from_str(
+                "a474f87de1645ff14c5e90c477b07f9bc86a22fb42909caa0705239298da96d0"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn deserialize_incoming_transfer_from_old_wallet() {
+        let transfer: IncomingTransfer = serde_json::from_value(serde_json::json!({
+            "amount": 60000000000000u64,
+            "global_index": 122405,
+            "key_image": "768f5144777eb23477ab7acf83562581d690abaf98ca897c03a9d2b900eb479b",
+            "spent": true,
+            "subaddr_index": { "major": 0, "minor": 0 },
+            "tx_hash": "f2c6b5fa2b2d6e33c8b9d0ea0fd5c1b4d3a6b0a9f7f5e5e5c5d5b5a595857565",
+            "tx_size": 2794
+        }))
+        .unwrap();
+        assert_eq!(transfer.global_index, 122405);
+        assert!(!transfer.frozen);
+        assert!(!transfer.unlocked);
+        assert!(transfer.pubkey.is_empty());
+    }
+
+    #[test]
+    fn deserialize_connection() {
+        let connection: Connection = serde_json::from_value(serde_json::json!({
+            "address": "95.216.203.255:18080",
+            "address_type": 1,
+            "avg_download": 0,
+            "avg_upload": 2,
+            "connection_id": "3e1a4d2f5b6c4d8e9f0a1b2c3d4e5f60",
+            "current_download": 0,
+            "current_upload": 0,
+            "height": 3195046,
+            "host": "95.216.203.255",
+            "incoming": false,
+            "ip": "95.216.203.255",
+            "live_time": 123,
+            "local_ip": false,
+            "localhost": false,
+            "peer_id": "cf5d0d7ae1bd1b3d",
+            "port": "18080",
+            "pruning_seed": 387,
+            "recv_count": 213842,
+            "recv_idle_time": 4,
+            "rpc_credits_per_hash": 0,
+            "rpc_port": 18089,
+            "send_count": 36201,
+            "send_idle_time": 4,
+            "state": "normal",
+            "support_flags": 1
+        }))
+        .unwrap();
+        assert_eq!(connection.state, ConnectionState::Normal);
+        assert_eq!(connection.live_time, 123);
+        assert_eq!(connection.pruning_seed, 387);
+        assert_eq!(connection.rpc_port, 18089);
+
+        let state: ConnectionState = serde_json::from_str("\"state_not_known_yet\"").unwrap();
+        assert_eq!(state, ConnectionState::Unknown);
+    }
+
+    #[test]
+    fn decode_output_distribution() {
+        assert_eq!(
+            decode_varints(&[0x01, 0xac, 0x02, 0x00]).unwrap(),
+            vec![1, 300, 0]
+        );
+        assert!(decode_varints(&[0x01, 0xac]).is_err());
+
+        let bytes = [1u64, 300, 3]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decode_distribution_blob(&bytes, false).unwrap(),
+            vec![1, 300, 3]
+        );
+        assert!(decode_distribution_blob(&bytes[1..], false).is_err());
+        assert_eq!(
+            decode_distribution_blob(&[0x01, 0xac, 0x02, 0xff, 0x01], true).unwrap(),
+            vec![1, 300, 255]
+        );
+
+        let list = serde_json::json!({
+            "amount": 0,
+            "base": 0,
+            "distribution": [1, 2, 3],
+            "start_height": 10
+        });
+        assert_eq!(
+            OutputDistribution::from(serde_json::from_value::<OutputDistributionR>(list).unwrap()),
+            OutputDistribution {
+                amount: Amount::ZERO,
+                base: 0,
+                distribution: vec![1, 2, 3],
+                start_height: 10,
+            }
+        );
+    }
+
+    fn block_template(reserve_size: usize) -> BlockTemplate {
+        use monero::{
+            blockdata::transaction::{
+                RawExtraField, Transaction, TransactionPrefix, TxIn, TxOut, TxOutTarget,
+            },
+            consensus::serialize,
+            util::ringct::RctSig,
+            Block, BlockHeader,
+        };
+
+        // tx public key, then the extra nonce field holding the reserved space
+        let mut extra = vec![0x01];
+        extra.extend_from_slice(&[7; 32]);
+        extra.push(TX_EXTRA_NONCE);
+        extra.extend_from_slice(&serialize(&VarInt(reserve_size as u64)));
+        extra.extend(vec![0; reserve_size]);
+
+        let block = Block {
+            header: BlockHeader {
+                major_version: VarInt(16),
+                minor_version: VarInt(16),
+                timestamp: VarInt(1_700_000_000),
+                prev_id: CryptoNoteHash([1; 32]),
+                nonce: 0,
+            },
+            miner_tx: Transaction {
+                prefix: TransactionPrefix {
+                    version: VarInt(1),
+                    unlock_time: VarInt(61),
+                    inputs: vec![TxIn::Gen { height: VarInt(1) }],
+                    outputs: vec![TxOut {
+                        amount: VarInt(1000),
+                        target: TxOutTarget::ToKey { key: [9; 32] },
+                    }],
+                    extra: RawExtraField(extra),
+                },
+                signatures: vec![],
+                rct_signatures: RctSig { sig: None, p: None },
+            },
+            tx_hashes: vec![CryptoNoteHash([2; 32]), CryptoNoteHash([3; 32])],
+        };
+        let blob = serialize(&block);
+        // the daemon points after the tx public key, the tag and a size of one byte
+        let reserved_offset = blob.windows(32).position(|w| w == [7; 32]).unwrap() + 32 + 2;
+
+        BlockTemplate {
+            blockhashing_blob: HashString(block.serialize_hashable()),
+            blocktemplate_blob: HashString(blob),
+            difficulty: 1,
+            expected_reward: Amount::from_pico(1000),
+            height: 1,
+            next_seed_hash: None,
+            prev_hash: HashString(BlockHash::repeat_byte(1)),
+            reserved_offset: reserved_offset as u64,
+            seed_hash: HashString(CryptoNoteHash::zero()),
+            seed_height: 0,
+            untrusted: false,
+            wide_difficulty: 1,
+        }
+    }
+
+    #[test]
+    fn block_template_helpers() {
+        let mut template = block_template(8);
+        assert_eq!(template.reserved_size(), 8);
+        assert_eq!(
+            template.hashing_blob().unwrap(),
+            template.blockhashing_blob.0
+        );
+
+        let hashing_blob = template.blockhashing_blob.0.clone();
+        template.set_extra_nonce(&[0xaa, 0xbb]).unwrap();
+        assert_ne!(template.blockhashing_blob.0, hashing_blob);
+        let block: monero::Block = deserialize(&template.blocktemplate_blob.0).unwrap();
+        assert!(block
+            .miner_tx
+            .prefix
+            .extra
+            .0
+            .ends_with(&[8, 0xaa, 0xbb, 0, 0, 0, 0, 0, 0]));
+        assert!(template.set_extra_nonce(&[0; 9]).is_err());
+
+        template.set_nonce(0xdeadbeef).unwrap();
+        let block: monero::Block =
+            deserialize(&hex::decode(template.submit_blob()).unwrap()).unwrap();
+        assert_eq!(block.header.nonce, 0xdeadbeef);
+        assert_eq!(block.serialize_hashable(), template.blockhashing_blob.0);
+
+        // sizes over 127 take two bytes
+        let mut template = block_template(200);
+        assert_eq!(template.reserved_size(), 200);
+        template.set_extra_nonce(&[0xcc; 200]).unwrap();
+        let block: monero::Block = deserialize(&template.blocktemplate_blob.0).unwrap();
+        assert!(block.miner_tx.prefix.extra.0.ends_with(&[0xcc; 200]));
+
+        let mut template = block_template(0);
+        template.reserved_offset = 0;
+        assert_eq!(template.reserved_size(), 0);
+        assert!(template.set_extra_nonce(&[1]).is_err());
+    }
 }
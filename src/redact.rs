@@ -0,0 +1,261 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Masking of secrets in requests and responses before they are logged.
+
+use jsonrpc_core::types::{response::Output, MethodCall, Params};
+use serde_json::Value;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Fields holding secrets in the parameters or results of any method: wallet passwords, RPC
+/// credentials, private keys, seeds, transaction keys, the transaction sets and multisig data
+/// from which keys or spending details can be derived, and the blobs of transactions which may
+/// not be relayed yet, e.g. with `do_not_relay`, and which anyone could relay like their metadata.
+const SENSITIVE_FIELDS: &[&str] = &[
+    "password",
+    "old_password",
+    "new_password",
+    "spendkey",
+    "viewkey",
+    "seed",
+    "seed_offset",
+    "tx_key",
+    "tx_key_list",
+    "tx_metadata",
+    "tx_metadata_list",
+    "tx_blob",
+    "tx_blob_list",
+    "tx_as_hex",
+    "signed_txset",
+    "unsigned_txset",
+    "multisig_info",
+    "multisig_txset",
+];
+
+/// Fields holding secrets in the results of specific methods only.
+const SENSITIVE_RESULT_FIELDS: &[(&str, &str)] = &[("query_key", "key")];
+
+/// How the value of a sensitive field is masked.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Mask {
+    /// Replace it with `[REDACTED]`, for logs.
+    Placeholder,
+    /// Replace hex strings with as many zeros, so that recorded fixtures can still be decoded
+    /// when replayed, and other values with `[REDACTED]`.
+    Zeros,
+}
+
+impl Mask {
+    fn apply(self, value: &Value) -> Value {
+        match (self, value) {
+            (Mask::Zeros, Value::String(s)) if s.chars().all(|c| c.is_ascii_hexdigit()) => {
+                "0".repeat(s.len()).into()
+            }
+            (Mask::Zeros, Value::Array(values)) => {
+                values.iter().map(|value| self.apply(value)).collect()
+            }
+            _ => REDACTED.into(),
+        }
+    }
+}
+
+/// Replace the values of the sensitive fields of `value`, at any depth.
+fn redact(value: &mut Value, extra_fields: &[&str], mask: Mask) {
+    match value {
+        Value::Object(object) => {
+            for (field, value) in object.iter_mut() {
+                if SENSITIVE_FIELDS.contains(&field.as_str())
+                    || extra_fields.contains(&field.as_str())
+                {
+                    *value = mask.apply(value);
+                } else {
+                    redact(value, extra_fields, mask);
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| redact(value, extra_fields, mask)),
+        _ => (),
+    }
+}
+
+fn result_fields(method: &str) -> Vec<&'static str> {
+    SENSITIVE_RESULT_FIELDS
+        .iter()
+        .filter(|(m, _)| *m == method)
+        .map(|(_, field)| *field)
+        .collect()
+}
+
+fn masked(mut value: Value, extra_fields: &[&str], mask: Mask) -> Value {
+    redact(&mut value, extra_fields, mask);
+    value
+}
+
+/// Parameters of a call, with secrets masked.
+pub(crate) fn params(params: &Params) -> Value {
+    masked(
+        serde_json::to_value(params).unwrap_or(Value::Null),
+        &[],
+        Mask::Placeholder,
+    )
+}
+
+/// JSON-RPC method call, with secrets masked.
+pub(crate) fn method_call(method_call: &MethodCall) -> Value {
+    masked(
+        serde_json::to_value(method_call).unwrap_or(Value::Null),
+        &[],
+        Mask::Placeholder,
+    )
+}
+
+/// Response of a call to `method`, with secrets masked.
+pub(crate) fn response(method: &str, response: &Value) -> Value {
+    masked(response.clone(), &result_fields(method), Mask::Placeholder)
+}
+
+/// JSON-RPC output of a call to `method`, with secrets masked.
+pub(crate) fn output(method: &str, output: &Output) -> Value {
+    masked(
+        serde_json::to_value(output).unwrap_or(Value::Null),
+        &result_fields(method),
+        Mask::Placeholder,
+    )
+}
+
+/// Parameters of a call as recorded in a fixture, with hex secrets zeroed.
+pub(crate) fn fixture_params(params: &Value) -> Value {
+    masked(params.clone(), &[], Mask::Zeros)
+}
+
+/// Response of a call to `method` as recorded in a fixture, with hex secrets zeroed.
+pub(crate) fn fixture_response(method: &str, response: &Value) -> Value {
+    masked(response.clone(), &result_fields(method), Mask::Zeros)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::types::{Id, Success, Version};
+    use serde_json::json;
+
+    #[test]
+    fn redact_method_call() {
+        let call = MethodCall {
+            jsonrpc: Some(Version::V2),
+            method: "generate_from_keys".to_string(),
+            params: Params::Map(
+                json!({
+                    "filename": "wallet",
+                    "address": "4...",
+                    "spendkey": "secret spend key",
+                    "viewkey": "secret view key",
+                    "password": "secret password",
+                })
+                .as_object()
+                .unwrap()
+                .clone(),
+            ),
+            id: Id::Num(0),
+        };
+
+        let redacted = method_call(&call);
+        assert_eq!(redacted["params"]["filename"], "wallet");
+        assert_eq!(redacted["params"]["spendkey"], REDACTED);
+        assert_eq!(redacted["params"]["viewkey"], REDACTED);
+        assert_eq!(redacted["params"]["password"], REDACTED);
+        assert!(!redacted.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn redact_output() {
+        let rsp = Output::Success(Success {
+            jsonrpc: Some(Version::V2),
+            result: json!({ "key": "secret key" }),
+            id: Id::Num(0),
+        });
+
+        assert_eq!(output("query_key", &rsp)["result"]["key"], REDACTED);
+        assert_eq!(output("get_version", &rsp)["result"]["key"], "secret key");
+        assert_eq!(
+            response("transfer_split", &json!({ "tx_key_list": ["secret"] }))["tx_key_list"],
+            REDACTED
+        );
+    }
+
+    #[test]
+    fn redact_transaction_sets_and_multisig() {
+        let rsp = json!({
+            "tx_hash": "public hash",
+            "tx_metadata": "secret",
+            "tx_blob": "secret",
+            "unsigned_txset": "secret",
+            "multisig_txset": "secret",
+        });
+        let redacted = response("transfer", &rsp);
+        assert_eq!(redacted["tx_hash"], "public hash");
+        assert!(!redacted.to_string().contains("secret"));
+
+        let rsp = json!({
+            "tx_metadata_list": ["secret"],
+            "tx_blob_list": ["secret"],
+            "signed_txset": "secret",
+            "multisig_info": "secret",
+        });
+        assert!(!response("transfer_split", &rsp)
+            .to_string()
+            .contains("secret"));
+
+        let params = Params::Map(
+            json!({ "multisig_info": ["secret"], "threshold": 2 })
+                .as_object()
+                .unwrap()
+                .clone(),
+        );
+        let redacted = super::params(&params);
+        assert_eq!(redacted["multisig_info"], REDACTED);
+        assert_eq!(redacted["threshold"], 2);
+
+        let params = Params::Map(
+            json!({ "tx_as_hex": "secret", "do_not_relay": true })
+                .as_object()
+                .unwrap()
+                .clone(),
+        );
+        let redacted = super::params(&params);
+        assert_eq!(redacted["tx_as_hex"], REDACTED);
+        assert_eq!(redacted["do_not_relay"], true);
+    }
+
+    #[test]
+    fn redact_fixture() {
+        let rsp = json!({
+            "key": "8ae33e57aee12fa4ad5b42a3ab093d9f3cb7f9be68b112a85f83275bcc5a190b",
+            "tx_key_list": ["aa", "bb"],
+            "password": "hunter2",
+        });
+        assert_eq!(
+            fixture_response("query_key", &rsp),
+            json!({
+                "key": "0".repeat(64),
+                "tx_key_list": ["00", "00"],
+                "password": REDACTED,
+            })
+        );
+        assert_eq!(fixture_params(&rsp)["key"], rsp["key"]);
+    }
+}
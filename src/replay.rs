@@ -0,0 +1,551 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{redact, Transport, TransportFuture};
+use jsonrpc_core::types::{response, MethodCall, Params};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+/// Endpoint family of a recorded exchange.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ExchangeKind {
+    JsonRpc,
+    Other,
+    Binary,
+}
+
+/// One request/response pair, stored as one line of a fixture file. Binary bodies are stored as
+/// hex strings, JSON-RPC responses without their id.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Exchange {
+    kind: ExchangeKind,
+    method: String,
+    params: Value,
+    response: Value,
+}
+
+/// [`Transport`] recording every request/response pair sent through another transport to a
+/// fixture file, to be served back later by a [`ReplayTransport`].
+///
+/// The file is written in the [JSON Lines](https://jsonlines.org) format, one exchange per line
+/// with its method, parameters and raw JSON response. Failed requests are not recorded. See also
+/// [`RpcClientBuilder::record_to`](crate::RpcClientBuilder::record_to).
+///
+/// Fixture files are meant to be shared, so the secrets found in JSON parameters and results
+/// (wallet passwords, private keys, seeds, transaction keys...) are masked by default, and are
+/// replayed as such: hex values are zeroed so that they still decode, e.g. a queried key is
+/// replayed as the zero key, and other values are replaced with a placeholder. Binary bodies are
+/// recorded as they are. Disable
+/// [`RecordingTransport::redact`] only for sessions without real secrets, e.g. against test
+/// wallets, and never commit such fixtures otherwise.
+#[derive(Debug)]
+pub struct RecordingTransport<T> {
+    inner: T,
+    file: Mutex<File>,
+    redact: bool,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Record the traffic of `inner` to the file at `path`, which is created or truncated.
+    pub fn new(inner: T, path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(Self {
+            inner,
+            file: Mutex::new(File::create(path)?),
+            redact: true,
+        })
+    }
+
+    /// Mask the secrets of the recorded JSON parameters and results, enabled by default.
+    pub fn redact(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    fn record(
+        &self,
+        kind: ExchangeKind,
+        method: &str,
+        params: Value,
+        response: Value,
+    ) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(&Exchange {
+            kind,
+            method: method.to_string(),
+            params,
+            response,
+        })?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&line)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    fn record_json_rpc(
+        &self,
+        method_call: &MethodCall,
+        output: &response::Output,
+    ) -> anyhow::Result<()> {
+        let mut params = serde_json::to_value(&method_call.params)?;
+        let mut response = serde_json::to_value(output)?;
+        if self.redact {
+            params = redact::fixture_params(&params);
+            response = redact::fixture_response(&method_call.method, &response);
+        }
+        if let Some(object) = response.as_object_mut() {
+            object.remove("id");
+        }
+        self.record(ExchangeKind::JsonRpc, &method_call.method, params, response)
+    }
+}
+
+impl<T: Transport> Transport for RecordingTransport<T> {
+    fn json_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method_call: &'a MethodCall,
+    ) -> TransportFuture<'a, response::Output> {
+        Box::pin(async move {
+            let rsp = self.inner.json_rpc(addr, method_call).await?;
+            self.record_json_rpc(method_call, &rsp)?;
+            Ok(rsp)
+        })
+    }
+
+    fn json_rpc_batch<'a>(
+        &'a self,
+        addr: &'a str,
+        method_calls: &'a [MethodCall],
+    ) -> TransportFuture<'a, Vec<response::Output>> {
+        Box::pin(async move {
+            let outputs = self.inner.json_rpc_batch(addr, method_calls).await?;
+            for output in &outputs {
+                let method_call = method_calls
+                    .iter()
+                    .find(|method_call| &method_call.id == output.id());
+                if let Some(method_call) = method_call {
+                    self.record_json_rpc(method_call, output)?;
+                }
+            }
+            Ok(outputs)
+        })
+    }
+
+    fn other_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method: &'a str,
+        params: &'a Params,
+    ) -> TransportFuture<'a, Value> {
+        Box::pin(async move {
+            let rsp = self.inner.other_rpc(addr, method, params).await?;
+            let params = serde_json::to_value(params)?;
+            if self.redact {
+                self.record(
+                    ExchangeKind::Other,
+                    method,
+                    redact::fixture_params(&params),
+                    redact::fixture_response(method, &rsp),
+                )?;
+            } else {
+                self.record(ExchangeKind::Other, method, params, rsp.clone())?;
+            }
+            Ok(rsp)
+        })
+    }
+
+    fn binary_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method: &'a str,
+        body: Vec<u8>,
+    ) -> TransportFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            let params = hex::encode(&body);
+            let rsp = self.inner.binary_rpc(addr, method, body).await?;
+            self.record(
+                ExchangeKind::Binary,
+                method,
+                params.into(),
+                hex::encode(&rsp).into(),
+            )?;
+            Ok(rsp)
+        })
+    }
+}
+
+/// [`Transport`] serving the responses recorded by a [`RecordingTransport`], without any network
+/// access.
+///
+/// Each request is answered with the first exchange of the fixture file not served yet that has
+/// the same method and parameters, so a method called several times with the same parameters
+/// gets its recorded responses in order, then the last one again. Parameters recorded with their
+/// secrets masked match the same parameters with any secret. Requests without such an exchange
+/// fail, unless [`ReplayTransport::lenient`] is set.
+///
+/// ```rust,no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use monero_rpc::{ReplayTransport, RpcClientBuilder};
+///
+/// let wallet = RpcClientBuilder::new()
+///     .transport(ReplayTransport::from_file("tests/fixtures/wallet.jsonl")?)
+///     .build("http://localhost:18083")?
+///     .wallet();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ReplayTransport {
+    exchanges: Mutex<Vec<(Exchange, bool)>>,
+    lenient: bool,
+}
+
+impl ReplayTransport {
+    /// Load the exchanges recorded in the fixture file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let exchanges = BufReader::new(File::open(path)?)
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|line| Ok((serde_json::from_str::<Exchange>(&line?)?, false)))
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            exchanges: Mutex::new(exchanges),
+            lenient: false,
+        })
+    }
+
+    /// When no exchange has the parameters of a request, answer with the next exchange of the
+    /// same method instead of failing, e.g. for parameters generated randomly at each run.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    fn response(&self, kind: ExchangeKind, method: &str, params: &Value) -> anyhow::Result<Value> {
+        let mut exchanges = self.exchanges.lock().unwrap();
+        let same_method = |exchange: &Exchange| exchange.kind == kind && exchange.method == method;
+        let redacted = redact::fixture_params(params);
+        let same_call = |exchange: &Exchange| {
+            same_method(exchange) && (exchange.params == *params || exchange.params == redacted)
+        };
+
+        // the first exchange not served yet, or else the last one served again
+        let find = |matches: &dyn Fn(&Exchange) -> bool| {
+            exchanges
+                .iter()
+                .position(|(exchange, served)| !served && matches(exchange))
+                .or_else(|| {
+                    exchanges
+                        .iter()
+                        .rposition(|(exchange, _)| matches(exchange))
+                })
+        };
+
+        let index = find(&same_call)
+            .or_else(|| {
+                if self.lenient {
+                    find(&same_method)
+                } else {
+                    None
+                }
+            })
+            .ok_or_else(|| {
+                anyhow::Error::msg(format!(
+                    "No recorded response for {} with params {}",
+                    method, params
+                ))
+            })?;
+
+        exchanges[index].1 = true;
+        Ok(exchanges[index].0.response.clone())
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn json_rpc<'a>(
+        &'a self,
+        _addr: &'a str,
+        method_call: &'a MethodCall,
+    ) -> TransportFuture<'a, response::Output> {
+        Box::pin(async move {
+            let params = serde_json::to_value(&method_call.params)?;
+            let mut response =
+                self.response(ExchangeKind::JsonRpc, &method_call.method, &params)?;
+            if let Some(object) = response.as_object_mut() {
+                object.insert("id".to_string(), serde_json::to_value(&method_call.id)?);
+            }
+            Ok(serde_json::from_value(response)?)
+        })
+    }
+
+    fn other_rpc<'a>(
+        &'a self,
+        _addr: &'a str,
+        method: &'a str,
+        params: &'a Params,
+    ) -> TransportFuture<'a, Value> {
+        Box::pin(async move {
+            self.response(ExchangeKind::Other, method, &serde_json::to_value(params)?)
+        })
+    }
+
+    fn binary_rpc<'a>(
+        &'a self,
+        _addr: &'a str,
+        method: &'a str,
+        body: Vec<u8>,
+    ) -> TransportFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            let response =
+                self.response(ExchangeKind::Binary, method, &hex::encode(body).into())?;
+            Ok(hex::decode(response.as_str().unwrap_or_default())?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockServer, PrivateKeyType, RpcClientBuilder, Secret};
+    use serde_json::json;
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    };
+
+    #[tokio::test]
+    async fn record_and_replay() {
+        let server = MockServer::start().await.unwrap();
+        let count = Arc::new(AtomicU64::new(10));
+        server
+            .json_rpc_handler("get_block_count", {
+                let count = count.clone();
+                move |_| {
+                    Ok(json!({
+                        "count": count.fetch_add(1, Ordering::Relaxed),
+                        "status": "OK",
+                    }))
+                }
+            })
+            .json_rpc_error("on_get_block_hash", -2, "Requested block height too big")
+            .other_rpc(
+                "get_transactions",
+                json!({
+                    "credits": 0,
+                    "top_hash": "",
+                    "status": "OK",
+                    "untrusted": false,
+                }),
+            );
+
+        let path = std::env::temp_dir().join(format!(
+            "monero-rpc-record-and-replay-{}.jsonl",
+            uuid::Uuid::new_v4()
+        ));
+
+        // record a session against the mock server
+        let client = RpcClientBuilder::new()
+            .record_to(&path)
+            .build(server.url())
+            .unwrap();
+        let daemon = client.clone().daemon();
+        assert_eq!(daemon.get_block_count().await.unwrap().get(), 10);
+        assert_eq!(daemon.get_block_count().await.unwrap().get(), 11);
+        assert!(daemon.on_get_block_hash(1000).await.is_err());
+        let rsp = client
+            .daemon_rpc()
+            .get_transactions(vec![], None, None)
+            .await
+            .unwrap();
+        drop(server);
+
+        // replay it without the server
+        let client = RpcClientBuilder::new()
+            .transport(ReplayTransport::from_file(&path).unwrap())
+            .build("http://unused")
+            .unwrap();
+        let daemon = client.clone().daemon();
+        assert_eq!(daemon.get_block_count().await.unwrap().get(), 10);
+        assert_eq!(daemon.get_block_count().await.unwrap().get(), 11);
+        assert_eq!(daemon.get_block_count().await.unwrap().get(), 11);
+        assert_eq!(
+            daemon
+                .on_get_block_hash(1000)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Server error: Requested block height too big"
+        );
+        assert_eq!(
+            client
+                .clone()
+                .daemon_rpc()
+                .get_transactions(vec![], None, None)
+                .await
+                .unwrap(),
+            rsp
+        );
+        assert_eq!(
+            daemon.on_get_block_hash(1).await.unwrap_err().to_string(),
+            "No recorded response for on_get_block_hash with params [1]"
+        );
+
+        // in lenient mode, requests with other parameters get the next response of the method
+        let daemon = RpcClientBuilder::new()
+            .transport(ReplayTransport::from_file(&path).unwrap().lenient(true))
+            .build("http://unused")
+            .unwrap()
+            .daemon();
+        assert_eq!(
+            daemon.on_get_block_hash(1).await.unwrap_err().to_string(),
+            "Server error: Requested block height too big"
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn record_and_replay_redacts_secrets() {
+        let server = MockServer::start().await.unwrap();
+        server.json_rpc("open_wallet", json!({})).json_rpc(
+            "query_key",
+            json!({ "key": "8ae33e57aee12fa4ad5b42a3ab093d9f3cb7f9be68b112a85f83275bcc5a190b" }),
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "monero-rpc-record-and-replay-redacts-secrets-{}.jsonl",
+            uuid::Uuid::new_v4()
+        ));
+
+        let wallet = RpcClientBuilder::new()
+            .record_to(&path)
+            .build(server.url())
+            .unwrap()
+            .wallet();
+        wallet
+            .open_wallet(
+                "wallet".to_string(),
+                Some(Secret::new("hunter2".to_string())),
+            )
+            .await
+            .unwrap();
+        wallet.query_key(PrivateKeyType::View).await.unwrap();
+        drop(server);
+
+        let fixture = std::fs::read_to_string(&path).unwrap();
+        assert!(!fixture.contains("hunter2"));
+        assert!(!fixture.contains("8ae33e57"));
+        assert_eq!(fixture.matches("[REDACTED]").count(), 1);
+        assert!(fixture.contains(&"0".repeat(64)));
+
+        // the masked password matches any password
+        let wallet = RpcClientBuilder::new()
+            .transport(ReplayTransport::from_file(&path).unwrap())
+            .build("http://unused")
+            .unwrap()
+            .wallet();
+        wallet
+            .open_wallet(
+                "wallet".to_string(),
+                Some(Secret::new("hunter3".to_string())),
+            )
+            .await
+            .unwrap();
+        assert!(wallet
+            .open_wallet(
+                "other".to_string(),
+                Some(Secret::new("hunter2".to_string()))
+            )
+            .await
+            .is_err());
+
+        // the zeroed key still decodes
+        let key = wallet.query_key(PrivateKeyType::View).await.unwrap();
+        assert_eq!(key.expose_secret().as_bytes(), &[0; 32]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn record_and_replay_binary() {
+        #[derive(Debug)]
+        struct Echo;
+
+        impl Transport for Echo {
+            fn json_rpc<'a>(
+                &'a self,
+                _addr: &'a str,
+                _method_call: &'a MethodCall,
+            ) -> TransportFuture<'a, response::Output> {
+                Box::pin(async { Err(anyhow::anyhow!("unexpected call")) })
+            }
+
+            fn other_rpc<'a>(
+                &'a self,
+                _addr: &'a str,
+                _method: &'a str,
+                _params: &'a Params,
+            ) -> TransportFuture<'a, Value> {
+                Box::pin(async { Err(anyhow::anyhow!("unexpected call")) })
+            }
+
+            fn binary_rpc<'a>(
+                &'a self,
+                _addr: &'a str,
+                _method: &'a str,
+                body: Vec<u8>,
+            ) -> TransportFuture<'a, Vec<u8>> {
+                Box::pin(async move { Ok(body.into_iter().rev().collect()) })
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "monero-rpc-record-and-replay-binary-{}.jsonl",
+            uuid::Uuid::new_v4()
+        ));
+
+        let recording = RecordingTransport::new(Echo, &path).unwrap();
+        assert_eq!(
+            recording
+                .binary_rpc("", "get_outs.bin", vec![1, 2, 3])
+                .await
+                .unwrap(),
+            vec![3, 2, 1]
+        );
+
+        let replay = ReplayTransport::from_file(&path).unwrap();
+        assert_eq!(
+            replay
+                .binary_rpc("", "get_outs.bin", vec![1, 2, 3])
+                .await
+                .unwrap(),
+            vec![3, 2, 1]
+        );
+        assert!(replay
+            .binary_rpc("", "get_outs.bin", vec![4])
+            .await
+            .is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
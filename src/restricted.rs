@@ -0,0 +1,214 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jsonrpc_core::types::error::ErrorCode;
+use std::fmt;
+
+/// Daemon methods disabled by `--restricted-rpc`, on `/json_rpc` or at their own extension.
+const RESTRICTED_METHODS: &[&str] = &[
+    // JSON-RPC
+    "generateblocks",
+    "get_connections",
+    "set_bans",
+    "get_bans",
+    "banned",
+    "flush_txpool",
+    "get_coinbase_tx_sum",
+    "get_alternate_chains",
+    "relay_tx",
+    "sync_info",
+    "prune_blockchain",
+    "flush_cache",
+    "calc_pow",
+    "add_aux_pow",
+    // other endpoints
+    "start_mining",
+    "stop_mining",
+    "mining_status",
+    "save_bc",
+    "set_log_hash_rate",
+    "set_log_level",
+    "set_log_categories",
+    "set_bootstrap_daemon",
+    "stop_daemon",
+    "get_net_stats",
+    "set_limit",
+    "out_peers",
+    "in_peers",
+    "pop_blocks",
+    "update",
+];
+
+/// True if `method` is disabled on daemons running with `--restricted-rpc`.
+pub(crate) fn is_restricted(method: &str) -> bool {
+    RESTRICTED_METHODS.contains(&method)
+}
+
+/// What a daemon allows, see [`DaemonJsonRpcClient::capabilities`].
+///
+/// [`DaemonJsonRpcClient::capabilities`]: crate::DaemonJsonRpcClient::capabilities
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DaemonCapabilities {
+    /// True if the daemon runs with `--restricted-rpc`, as public nodes do.
+    pub restricted: bool,
+}
+
+impl DaemonCapabilities {
+    /// True if `method`, e.g. `get_connections`, can be called on the daemon.
+    pub fn supports(&self, method: &str) -> bool {
+        !(self.restricted && is_restricted(method))
+    }
+}
+
+/// Error returned when calling a method disabled on a daemon running with `--restricted-rpc`.
+///
+/// It can be told apart from other errors with [`anyhow::Error::downcast_ref`]:
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// use monero_rpc::{monero::Address, RestrictedMethodError, RpcClientBuilder};
+/// use std::str::FromStr;
+///
+/// let daemon = RpcClientBuilder::new()
+///     .build("http://node.monerooutreach.org:18081")?
+///     .daemon()
+///     .regtest();
+/// let address = Address::from_str("4AdUndXHHZ6cfufTMvppY6JwXNouMBzSkbLYfpAV5Usx3skxNgYeYTRj5UzqtReoS44qo9mtmXCqY45DJ852K5Jv2684Rge")?;
+///
+/// if let Err(e) = daemon.generate_blocks(1, address).await {
+///     if let Some(e) = e.downcast_ref::<RestrictedMethodError>() {
+///         println!("{} needs a node of our own", e.method);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RestrictedMethodError {
+    /// Name of the method, e.g. `get_connections`.
+    pub method: String,
+}
+
+impl fmt::Display for RestrictedMethodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Method {} is not available on a restricted node",
+            self.method
+        )
+    }
+}
+
+impl std::error::Error for RestrictedMethodError {}
+
+/// Turn the "method not found" error of a restricted JSON-RPC method into a
+/// [`RestrictedMethodError`].
+pub(crate) fn json_rpc_error(method: &str, error: jsonrpc_core::Error) -> anyhow::Error {
+    if error.code == ErrorCode::MethodNotFound && is_restricted(method) {
+        RestrictedMethodError {
+            method: method.to_string(),
+        }
+        .into()
+    } else {
+        error.into()
+    }
+}
+
+/// Turn the "404 Not Found" error of a restricted endpoint into a [`RestrictedMethodError`].
+pub(crate) fn daemon_rpc_error(method: &str, error: anyhow::Error) -> anyhow::Error {
+    let not_found = error
+        .downcast_ref::<reqwest::Error>()
+        .and_then(reqwest::Error::status)
+        == Some(reqwest::StatusCode::NOT_FOUND);
+
+    if not_found && is_restricted(method) {
+        RestrictedMethodError {
+            method: method.to_string(),
+        }
+        .into()
+    } else {
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockServer, RpcClientBuilder};
+    use serde_json::json;
+
+    #[test]
+    fn daemon_capabilities_supports() {
+        let restricted = DaemonCapabilities { restricted: true };
+        assert!(restricted.supports("get_block_count"));
+        assert!(!restricted.supports("get_connections"));
+        assert!(!restricted.supports("stop_daemon"));
+        assert!(!restricted.supports("add_aux_pow"));
+        assert!(DaemonCapabilities { restricted: false }.supports("get_connections"));
+    }
+
+    #[tokio::test]
+    async fn restricted_node() {
+        let server = MockServer::start().await.unwrap();
+        server.json_rpc(
+            "get_info",
+            json!({ "restricted": true, "height": 100, "status": "OK" }),
+        );
+        let daemon = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon();
+
+        let capabilities = daemon.capabilities().await.unwrap();
+        assert!(capabilities.restricted);
+        assert!(!capabilities.supports("generateblocks"));
+
+        let address = "4AdUndXHHZ6cfufTMvppY6JwXNouMBzSkbLYfpAV5Usx3skxNgYeYTRj5UzqtReoS44qo9mtmXCqY45DJ852K5Jv2684Rge".parse().unwrap();
+        let err = daemon
+            .clone()
+            .regtest()
+            .generate_blocks(1, address)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RestrictedMethodError>(),
+            Some(&RestrictedMethodError {
+                method: "generateblocks".to_string()
+            })
+        );
+
+        // unknown methods that are not restricted keep their error
+        let err = daemon.get_version().await.unwrap_err();
+        assert!(err.downcast_ref::<RestrictedMethodError>().is_none());
+    }
+
+    #[tokio::test]
+    async fn capabilities_probe() {
+        // nodes not reporting the flag are probed with a restricted method
+        let server = MockServer::start().await.unwrap();
+        server.json_rpc("get_info", json!({ "height": 100, "status": "OK" }));
+        let daemon = RpcClientBuilder::new()
+            .build(server.url())
+            .unwrap()
+            .daemon();
+        assert!(daemon.capabilities().await.unwrap().restricted);
+
+        server.json_rpc(
+            "get_connections",
+            json!({ "connections": [], "status": "OK" }),
+        );
+        assert!(!daemon.capabilities().await.unwrap().restricted);
+    }
+}
@@ -0,0 +1,404 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::failover::NodeResponse;
+use rand::Rng;
+use std::{collections::HashSet, future::Future, time::Duration};
+use tracing::*;
+
+/// Methods that change state on the daemon or the wallet, and that must not be sent twice.
+///
+/// Endpoints outside of JSON-RPC, such as `stop_daemon`, are named as in their path, which is
+/// also the name given to [`RetryPolicy`] by daemon RPC calls.
+const NON_IDEMPOTENT_METHODS: &[&str] = &[
+    "transfer",
+    "transfer_split",
+    "sweep_all",
+    "sweep_single",
+    "sweep_dust",
+    "relay_tx",
+    "submit_transfer",
+    "submit_block",
+    "send_raw_transaction",
+    "create_account",
+    "create_address",
+    "generateblocks",
+    "stop_wallet",
+    "stop_daemon",
+    "create_wallet",
+    "generate_from_keys",
+    "restore_deterministic_wallet",
+    "change_wallet_password",
+    "rescan_blockchain",
+    "set_bans",
+    "start_mining",
+    "stop_mining",
+    "add_aux_pow",
+];
+
+/// Class of a failed RPC call.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ErrorClass {
+    /// The connection to the node could not be established, the request was not sent.
+    Connect,
+    /// The request timed out.
+    Timeout,
+    /// The response could not be decoded.
+    Decode,
+    /// Any other transport error.
+    Transport,
+    /// The node answered with a `BUSY` status and did not process the request.
+    Busy,
+}
+
+impl ErrorClass {
+    /// Classify an error returned by a call, `None` if it is not a transport error.
+    pub fn of(error: &anyhow::Error) -> Option<Self> {
+        let error = reqwest_error(error)?;
+
+        Some(if error.is_connect() {
+            ErrorClass::Connect
+        } else if error.is_timeout() {
+            ErrorClass::Timeout
+        } else if error.is_decode() {
+            ErrorClass::Decode
+        } else {
+            ErrorClass::Transport
+        })
+    }
+
+    /// Name of the class, as used in traces and metrics.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorClass::Connect => "connect",
+            ErrorClass::Timeout => "timeout",
+            ErrorClass::Decode => "decode",
+            ErrorClass::Transport => "transport",
+            ErrorClass::Busy => "busy",
+        }
+    }
+
+    /// True if a call failing with this class was certainly not processed by the node, so it is
+    /// safe to send it again even if it is not idempotent.
+    fn is_unprocessed(self) -> bool {
+        matches!(self, ErrorClass::Connect | ErrorClass::Busy)
+    }
+}
+
+/// True if a call to `method` failing with `class` may be sent again, to the same node or to
+/// another one: it is idempotent, or it was certainly not processed.
+pub(crate) fn may_resend(method: &str, class: Option<ErrorClass>) -> bool {
+    class.map_or(false, ErrorClass::is_unprocessed) || !NON_IDEMPOTENT_METHODS.contains(&method)
+}
+
+/// The [`reqwest::Error`] behind `error`, also when wrapped by the digest authentication.
+fn reqwest_error(error: &anyhow::Error) -> Option<&reqwest::Error> {
+    if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        return Some(error);
+    }
+    #[cfg(feature = "rpc_authentication")]
+    if let Some(diqwest::error::Error::Reqwest(error)) =
+        error.downcast_ref::<diqwest::error::Error>()
+    {
+        return Some(error);
+    }
+    None
+}
+
+/// Configure how failed calls are retried, see [`RpcClientBuilder::retry_policy`].
+///
+/// Calls are retried with an exponential backoff when they fail with one of the retryable
+/// [`ErrorClass`]es. Methods that are not idempotent, such as `transfer`, `relay_tx` or
+/// `submit_block`, are only retried when the node certainly did not process them, i.e. on
+/// [`ErrorClass::Connect`] and [`ErrorClass::Busy`], unless
+/// [`RetryPolicy::retry_non_idempotent`] is set.
+///
+/// ```rust
+/// # fn main() -> anyhow::Result<()> {
+/// use monero_rpc::{ErrorClass, RetryPolicy, RpcClientBuilder};
+/// use std::time::Duration;
+///
+/// let client = RpcClientBuilder::new()
+///     .retry_policy(
+///         RetryPolicy::new()
+///             .max_attempts(5)
+///             .initial_backoff(Duration::from_millis(200))
+///             .retryable([ErrorClass::Connect, ErrorClass::Timeout, ErrorClass::Busy]),
+///     )
+///     .build("http://node.monerooutreach.org:18081")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`RpcClientBuilder::retry_policy`]: crate::RpcClientBuilder::retry_policy
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    retryable: HashSet<ErrorClass>,
+    retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy making up to 3 attempts, with a backoff starting at 100ms and capped at
+    /// 2s, retrying connection errors, timeouts and `BUSY` answers.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            jitter: true,
+            retryable: [ErrorClass::Connect, ErrorClass::Timeout, ErrorClass::Busy]
+                .into_iter()
+                .collect(),
+            retry_non_idempotent: false,
+        }
+    }
+
+    /// Maximum number of attempts for one call, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Backoff before the second attempt, doubled for each following attempt.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Upper bound of the backoff between two attempts.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Randomize each backoff between half and all of its value.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Classes of errors on which calls are retried.
+    pub fn retryable(mut self, classes: impl IntoIterator<Item = ErrorClass>) -> Self {
+        self.retryable = classes.into_iter().collect();
+        self
+    }
+
+    /// Also retry non-idempotent methods on errors after which the node may have processed
+    /// the call. Only set this if sending the same call twice is harmless to you.
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+
+        if self.jitter && !backoff.is_zero() {
+            rand::thread_rng().gen_range(backoff / 2..=backoff)
+        } else {
+            backoff
+        }
+    }
+
+    fn should_retry(&self, method: &str, class: ErrorClass) -> bool {
+        self.retryable.contains(&class)
+            && (self.retry_non_idempotent || may_resend(method, Some(class)))
+    }
+
+    /// Run `call` until it succeeds, fails with an error that is not retryable, or the maximum
+    /// number of attempts is reached.
+    pub(crate) async fn run<F, Fut, T>(&self, method: &str, call: F) -> anyhow::Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+        T: NodeResponse,
+    {
+        let mut attempt = 1;
+        loop {
+            let result = call().await;
+
+            let class = match &result {
+                Ok(rsp) if rsp.is_busy() => Some(ErrorClass::Busy),
+                Ok(_) => None,
+                Err(e) => ErrorClass::of(e),
+            };
+
+            match class {
+                Some(class) if attempt < self.max_attempts && self.should_retry(method, class) => {
+                    let backoff = self.backoff(attempt);
+                    debug!(
+                        "Retrying {} in {:?} after {:?} error (attempt {}/{})",
+                        method, backoff, class, attempt, self.max_attempts
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[derive(Debug)]
+    struct Rsp(bool);
+
+    impl NodeResponse for Rsp {
+        fn is_busy(&self) -> bool {
+            self.0
+        }
+    }
+
+    async fn connect_error() -> anyhow::Error {
+        // nothing listens on port 1, the connection is refused
+        reqwest::get("http://127.0.0.1:1").await.unwrap_err().into()
+    }
+
+    #[tokio::test]
+    async fn error_class_of_connect_error() {
+        assert_eq!(
+            ErrorClass::of(&connect_error().await),
+            Some(ErrorClass::Connect)
+        );
+        assert_eq!(ErrorClass::of(&anyhow::Error::msg("not transport")), None);
+    }
+
+    #[cfg(feature = "rpc_authentication")]
+    #[tokio::test]
+    async fn error_class_of_digest_auth_error() {
+        let error = reqwest::get("http://127.0.0.1:1").await.unwrap_err();
+        assert_eq!(
+            ErrorClass::of(&diqwest::error::Error::Reqwest(error).into()),
+            Some(ErrorClass::Connect)
+        );
+        assert_eq!(
+            ErrorClass::of(&diqwest::error::Error::AuthHeaderMissing.into()),
+            None
+        );
+    }
+
+    #[test]
+    fn retry_policy_backoff() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(300))
+            .jitter(false);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(300));
+        assert_eq!(policy.backoff(40), Duration::from_millis(300));
+
+        let policy = policy.jitter(true);
+        for attempt in 1..5 {
+            let backoff = policy.backoff(attempt);
+            assert!(backoff >= Duration::from_millis(50));
+            assert!(backoff <= Duration::from_millis(300));
+        }
+    }
+
+    #[test]
+    fn retry_policy_idempotency() {
+        let policy = RetryPolicy::new();
+
+        assert!(policy.should_retry("get_block_count", ErrorClass::Timeout));
+        assert!(!policy.should_retry("transfer", ErrorClass::Timeout));
+        assert!(!policy.should_retry("submit_block", ErrorClass::Timeout));
+        assert!(policy.should_retry("relay_tx", ErrorClass::Connect));
+        assert!(policy.should_retry("relay_tx", ErrorClass::Busy));
+        assert!(!policy.should_retry("get_block_count", ErrorClass::Decode));
+        for method in [
+            "stop_daemon",
+            "create_wallet",
+            "generate_from_keys",
+            "restore_deterministic_wallet",
+            "change_wallet_password",
+            "rescan_blockchain",
+            "set_bans",
+            "start_mining",
+            "stop_mining",
+            "add_aux_pow",
+        ] {
+            assert!(
+                !policy.should_retry(method, ErrorClass::Timeout),
+                "{}",
+                method
+            );
+            assert!(
+                policy.should_retry(method, ErrorClass::Connect),
+                "{}",
+                method
+            );
+        }
+
+        let policy = policy.retry_non_idempotent(true);
+        assert!(policy.should_retry("transfer", ErrorClass::Timeout));
+    }
+
+    #[tokio::test]
+    async fn retry_policy_run() {
+        let policy = RetryPolicy::new()
+            .max_attempts(3)
+            .initial_backoff(Duration::ZERO);
+
+        // connection errors are retried until the maximum number of attempts
+        let attempts = AtomicU32::new(0);
+        let result = policy
+            .run("get_block_count", || async {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                Err::<Rsp, _>(connect_error().await)
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+
+        // busy answers are retried until the node is ready
+        let attempts = AtomicU32::new(0);
+        let result = policy
+            .run("submit_block", || async {
+                Ok(Rsp(attempts.fetch_add(1, Ordering::Relaxed) == 0))
+            })
+            .await;
+        assert!(!result.unwrap().is_busy());
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+
+        // other errors are returned right away
+        let attempts = AtomicU32::new(0);
+        let result = policy
+            .run("get_block_count", || async {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                Err::<Rsp, _>(anyhow::Error::msg("not transport"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::Relaxed), 1);
+    }
+}
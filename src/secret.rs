@@ -0,0 +1,164 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{CryptoNoteHash, HashString};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{self, Debug};
+use zeroize::Zeroize;
+
+/// Value that can be overwritten in place when a [`Secret`] holding it is dropped.
+pub trait Wipe {
+    /// Overwrite the value with zeros.
+    fn wipe(&mut self);
+}
+
+impl Wipe for String {
+    fn wipe(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Wipe for Vec<u8> {
+    fn wipe(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Wipe for monero::PrivateKey {
+    fn wipe(&mut self) {
+        self.scalar.zeroize();
+    }
+}
+
+impl Wipe for CryptoNoteHash {
+    fn wipe(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Wipe> Wipe for HashString<T> {
+    fn wipe(&mut self) {
+        self.0.wipe();
+    }
+}
+
+/// Secret value, such as a wallet password, a private key or a seed: it is overwritten with
+/// zeros when dropped, and printed as `Secret([REDACTED])` by `Debug`.
+///
+/// Use [`Secret::expose_secret`] to read the value. Keep in mind that copies made from it, e.g.
+/// of a `PrivateKey` which is `Copy`, are not wiped.
+///
+/// ```rust
+/// use monero_rpc::Secret;
+///
+/// let password = Secret::from("hunter2");
+/// assert_eq!(password.expose_secret(), "hunter2");
+/// assert_eq!(format!("{:?}", password), "Secret([REDACTED])");
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct Secret<T: Wipe>(T);
+
+impl<T: Wipe> Secret<T> {
+    /// Wraps a secret value.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns the secret value.
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Wipe> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl<T: Wipe> Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+impl<T: Wipe> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
+
+impl<T: Wipe + Serialize> Serialize for Secret<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Wipe + Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn secret_debug_is_redacted() {
+        let key = monero::PrivateKey::from_str(
+            "8ae33e57aee12fa4ad5b42a3ab093d9f3cb7f9be68b112a85f83275bcc5a190b",
+        )
+        .unwrap();
+        let args = (Secret::new(key), Secret::from("hunter2"));
+
+        let debug = format!("{:?}", args);
+        assert_eq!(debug, "(Secret([REDACTED]), Secret([REDACTED]))");
+        assert_eq!(args.0.expose_secret(), &key);
+    }
+
+    #[test]
+    fn secret_wipe() {
+        let mut key = monero::PrivateKey::from_str(
+            "8ae33e57aee12fa4ad5b42a3ab093d9f3cb7f9be68b112a85f83275bcc5a190b",
+        )
+        .unwrap();
+        key.wipe();
+        assert_eq!(key.as_bytes(), &[0; 32]);
+
+        let mut seed = String::from("roster ghost powder");
+        seed.wipe();
+        assert!(seed.is_empty());
+    }
+
+    #[test]
+    fn secret_serde_is_transparent() {
+        let secret: Secret<String> = serde_json::from_str("\"hunter2\"").unwrap();
+        assert_eq!(secret.expose_secret(), "hunter2");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"hunter2\"");
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracing spans and metrics of RPC calls, see the crate documentation.
+
+use crate::{failover::NodeResponse, ErrorClass};
+use serde_json::Value;
+use std::time::Instant;
+use tracing::{field, Span, *};
+
+/// Outcome of an RPC call.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum CallStatus {
+    /// The node answered successfully.
+    Ok,
+    /// The response was served from the cache.
+    Cached,
+    /// The node answered with a JSON-RPC error.
+    RpcError,
+    /// The node answered with a `BUSY` status.
+    Busy,
+    /// The call failed before getting an answer.
+    Error(Option<ErrorClass>),
+}
+
+impl CallStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            CallStatus::Ok => "ok",
+            CallStatus::Cached => "cached",
+            CallStatus::RpcError => "rpc_error",
+            CallStatus::Busy => "busy",
+            CallStatus::Error(_) => "error",
+        }
+    }
+
+    /// Status of a JSON-RPC call.
+    pub(crate) fn of_json_rpc(result: &anyhow::Result<jsonrpc_core::Result<Value>>) -> Self {
+        match result {
+            Ok(Ok(value)) => Self::of_value(value),
+            Ok(Err(_)) => CallStatus::RpcError,
+            Err(e) => CallStatus::Error(ErrorClass::of(e)),
+        }
+    }
+
+    /// Status of a call to an endpoint called with its own extension.
+    pub(crate) fn of_daemon_rpc(result: &anyhow::Result<Value>) -> Self {
+        match result {
+            Ok(value) => Self::of_value(value),
+            Err(e) => CallStatus::Error(ErrorClass::of(e)),
+        }
+    }
+
+    /// Status of a call to a binary endpoint.
+    pub(crate) fn of_binary_rpc(result: &anyhow::Result<Vec<u8>>) -> Self {
+        match result {
+            Ok(_) => CallStatus::Ok,
+            Err(e) => CallStatus::Error(ErrorClass::of(e)),
+        }
+    }
+
+    fn of_value(value: &Value) -> Self {
+        if value.is_busy() {
+            CallStatus::Busy
+        } else {
+            CallStatus::Ok
+        }
+    }
+}
+
+/// Span of one call, to be entered by the future sending it.
+pub(crate) fn call_span(method: &str, request_id: Option<&str>) -> Span {
+    let span = debug_span!(
+        "rpc_call",
+        method,
+        request_id = field::Empty,
+        endpoint = field::Empty,
+        duration_ms = field::Empty,
+        status = field::Empty,
+        error_class = field::Empty,
+    );
+    if let Some(request_id) = request_id {
+        span.record("request_id", request_id);
+    }
+    span
+}
+
+/// Record the node a request is sent to on the span of its call.
+pub(crate) fn record_endpoint(span: &Span, addr: &str) {
+    span.record("endpoint", addr);
+}
+
+/// Record the outcome of a call on its span and in metrics.
+pub(crate) fn record_call(span: &Span, method: &'static str, start: Instant, status: CallStatus) {
+    let duration = start.elapsed();
+
+    span.record("duration_ms", duration.as_millis() as u64);
+    span.record("status", status.as_str());
+    if let CallStatus::Error(Some(class)) = status {
+        span.record("error_class", class.as_str());
+    }
+    span.in_scope(|| {
+        debug!(
+            "RPC call {} finished in {:?} with status {}",
+            method,
+            duration,
+            status.as_str()
+        )
+    });
+
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!(
+            "monero_rpc_requests_total",
+            "method" => method,
+            "status" => status.as_str(),
+        )
+        .increment(1);
+        metrics::histogram!("monero_rpc_request_duration_seconds", "method" => method)
+            .record(duration.as_secs_f64());
+        if let CallStatus::Error(class) = status {
+            metrics::counter!(
+                "monero_rpc_errors_total",
+                "method" => method,
+                "error_class" => class.map_or("other", ErrorClass::as_str),
+            )
+            .increment(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn call_status() {
+        assert_eq!(
+            CallStatus::of_json_rpc(&Ok(Ok(json!({ "status": "OK" })))),
+            CallStatus::Ok
+        );
+        assert_eq!(
+            CallStatus::of_json_rpc(&Ok(Ok(json!({ "status": "BUSY" })))),
+            CallStatus::Busy
+        );
+        assert_eq!(
+            CallStatus::of_json_rpc(&Ok(Err(jsonrpc_core::Error::method_not_found()))),
+            CallStatus::RpcError
+        );
+        assert_eq!(
+            CallStatus::of_daemon_rpc(&Err(anyhow::Error::msg("not transport"))),
+            CallStatus::Error(None)
+        );
+    }
+}
@@ -0,0 +1,370 @@
+// Copyright 2019-2023 Artem Vorotnikov and Monero Rust Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "rpc_authentication")]
+use crate::RpcAuthentication;
+use jsonrpc_core::types::{
+    response::{self, Output, Response},
+    ErrorCode, MethodCall, Params,
+};
+use serde_json::Value;
+use std::{
+    fmt::{self, Debug},
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+};
+
+#[cfg(feature = "rpc_authentication")]
+use diqwest::WithDigestAuth;
+
+/// Error returned by [`Transport::json_rpc_batch`] when the node does not support JSON-RPC
+/// batches, as monerod and wallet-rpc. The calls of the batch are then sent one by one with
+/// [`Transport::json_rpc`], each under the limits of its own method.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BatchNotSupported;
+
+impl fmt::Display for BatchNotSupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("JSON-RPC batches are not supported by the node")
+    }
+}
+
+impl std::error::Error for BatchNotSupported {}
+
+/// Future returned by the methods of [`Transport`].
+pub type TransportFuture<'a, T> = Pin<Box<dyn Future<Output = anyhow::Result<T>> + Send + 'a>>;
+
+/// Sends requests to a node on behalf of an [`RpcClient`](crate::RpcClient).
+///
+/// [`HttpTransport`] is used by default. Implement this trait to reach nodes by other means,
+/// e.g. through a Unix socket or an SSH tunnel, or to record or fake the traffic in tests, and
+/// pass it to [`RpcClientBuilder::transport`](crate::RpcClientBuilder::transport). `addr` is the
+/// address given to the builder.
+///
+/// Errors are classified by [`ErrorClass::of`](crate::ErrorClass::of) for retries: return the
+/// underlying [`reqwest::Error`] where there is one, other errors are never retried.
+pub trait Transport: Debug + Send + Sync {
+    /// Send a JSON-RPC method call to the `/json_rpc` endpoint.
+    fn json_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method_call: &'a MethodCall,
+    ) -> TransportFuture<'a, response::Output>;
+
+    /// Send several JSON-RPC method calls to the `/json_rpc` endpoint at once. Outputs may be
+    /// returned in any order, they are matched to the calls by id.
+    ///
+    /// Return [`BatchNotSupported`] if the node rejects batches, which is what the default
+    /// implementation does: the client then sends the calls one after the other with
+    /// [`Transport::json_rpc`].
+    fn json_rpc_batch<'a>(
+        &'a self,
+        _addr: &'a str,
+        _method_calls: &'a [MethodCall],
+    ) -> TransportFuture<'a, Vec<response::Output>> {
+        Box::pin(async { Err(BatchNotSupported.into()) })
+    }
+
+    /// Send a JSON request to an endpoint called with its own extension, such as
+    /// `/get_transactions`.
+    fn other_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method: &'a str,
+        params: &'a Params,
+    ) -> TransportFuture<'a, Value>;
+
+    /// Send a binary request to an endpoint called with its own extension, such as
+    /// `/get_outs.bin`.
+    fn binary_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method: &'a str,
+        body: Vec<u8>,
+    ) -> TransportFuture<'a, Vec<u8>>;
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn json_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method_call: &'a MethodCall,
+    ) -> TransportFuture<'a, response::Output> {
+        (**self).json_rpc(addr, method_call)
+    }
+
+    fn json_rpc_batch<'a>(
+        &'a self,
+        addr: &'a str,
+        method_calls: &'a [MethodCall],
+    ) -> TransportFuture<'a, Vec<response::Output>> {
+        (**self).json_rpc_batch(addr, method_calls)
+    }
+
+    fn other_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method: &'a str,
+        params: &'a Params,
+    ) -> TransportFuture<'a, Value> {
+        (**self).other_rpc(addr, method, params)
+    }
+
+    fn binary_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method: &'a str,
+        body: Vec<u8>,
+    ) -> TransportFuture<'a, Vec<u8>> {
+        (**self).binary_rpc(addr, method, body)
+    }
+}
+
+/// Default [`Transport`], sending requests over HTTP with [`reqwest`].
+#[derive(Clone, Debug)]
+pub struct HttpTransport {
+    http_client: reqwest::Client,
+    #[cfg(feature = "rpc_authentication")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rpc_authentication")))]
+    rpc_auth: RpcAuthentication,
+}
+
+impl HttpTransport {
+    /// Creates a transport sending requests with the given HTTP client.
+    pub fn new(http_client: reqwest::Client) -> Self {
+        Self {
+            http_client,
+            #[cfg(feature = "rpc_authentication")]
+            rpc_auth: RpcAuthentication::None,
+        }
+    }
+
+    /// Configures the authentication to use when connecting to RPC.
+    #[cfg(feature = "rpc_authentication")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rpc_authentication")))]
+    pub fn rpc_authentication(mut self, auth: RpcAuthentication) -> Self {
+        self.rpc_auth = auth;
+        self
+    }
+
+    async fn send(&self, req: reqwest::RequestBuilder) -> anyhow::Result<reqwest::Response> {
+        #[cfg(not(feature = "rpc_authentication"))]
+        let rsp = req.send().await?;
+
+        // transport errors are returned as plain `reqwest::Error`s, to be classified for retries
+        // and metrics whatever the authentication
+        #[cfg(feature = "rpc_authentication")]
+        let rsp = if let RpcAuthentication::Credentials { username, password } = &self.rpc_auth {
+            match req.send_with_digest_auth(username, password).await {
+                Ok(rsp) => rsp,
+                Err(diqwest::error::Error::Reqwest(e)) => return Err(e.into()),
+                Err(e) => return Err(e.into()),
+            }
+        } else {
+            req.send().await?
+        };
+
+        Ok(rsp)
+    }
+}
+
+impl Transport for HttpTransport {
+    fn json_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method_call: &'a MethodCall,
+    ) -> TransportFuture<'a, response::Output> {
+        Box::pin(async move {
+            let uri = format!("{}/json_rpc", addr);
+            let req = self.http_client.post(&uri).json(method_call);
+            Ok(self.send(req).await?.json::<response::Output>().await?)
+        })
+    }
+
+    fn json_rpc_batch<'a>(
+        &'a self,
+        addr: &'a str,
+        method_calls: &'a [MethodCall],
+    ) -> TransportFuture<'a, Vec<response::Output>> {
+        Box::pin(async move {
+            let uri = format!("{}/json_rpc", addr);
+            let req = self.http_client.post(&uri).json(method_calls);
+            match self.send(req).await?.json::<Response>().await? {
+                Response::Batch(outputs) => Ok(outputs),
+                // the whole batch was rejected by a node not supporting batches
+                Response::Single(Output::Failure(failure))
+                    if matches!(
+                        failure.error.code,
+                        ErrorCode::ParseError | ErrorCode::InvalidRequest
+                    ) =>
+                {
+                    Err(BatchNotSupported.into())
+                }
+                Response::Single(Output::Failure(failure)) => Err(failure.error.into()),
+                Response::Single(output) => Ok(vec![output]),
+            }
+        })
+    }
+
+    fn other_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method: &'a str,
+        params: &'a Params,
+    ) -> TransportFuture<'a, Value> {
+        Box::pin(async move {
+            let uri = format!("{}/{}", addr, method);
+            let req = self.http_client.post(uri).json(params);
+            Ok(self
+                .send(req)
+                .await?
+                .error_for_status()?
+                .json::<Value>()
+                .await?)
+        })
+    }
+
+    fn binary_rpc<'a>(
+        &'a self,
+        addr: &'a str,
+        method: &'a str,
+        body: Vec<u8>,
+    ) -> TransportFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            let uri = format!("{}/{}", addr, method);
+            let req = self
+                .http_client
+                .post(uri)
+                .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+                .body(body);
+            Ok(self
+                .send(req)
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?
+                .to_vec())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RpcClientBuilder;
+    use jsonrpc_core::types::{Success, Version};
+    use serde_json::json;
+
+    #[derive(Debug)]
+    struct FakeTransport;
+
+    impl Transport for FakeTransport {
+        fn json_rpc<'a>(
+            &'a self,
+            addr: &'a str,
+            method_call: &'a MethodCall,
+        ) -> TransportFuture<'a, response::Output> {
+            Box::pin(async move {
+                assert_eq!(addr, "fake");
+                assert_eq!(method_call.method, "get_block_count");
+                Ok(response::Output::Success(Success {
+                    jsonrpc: Some(Version::V2),
+                    result: json!({ "count": 42, "status": "OK" }),
+                    id: method_call.id.clone(),
+                }))
+            })
+        }
+
+        fn other_rpc<'a>(
+            &'a self,
+            _addr: &'a str,
+            method: &'a str,
+            _params: &'a Params,
+        ) -> TransportFuture<'a, Value> {
+            Box::pin(async move { Err(anyhow::Error::msg(format!("unexpected {}", method))) })
+        }
+
+        fn binary_rpc<'a>(
+            &'a self,
+            _addr: &'a str,
+            _method: &'a str,
+            body: Vec<u8>,
+        ) -> TransportFuture<'a, Vec<u8>> {
+            Box::pin(async move { Ok(body) })
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_transport() {
+        let daemon = RpcClientBuilder::new()
+            .transport(FakeTransport)
+            .build("fake")
+            .unwrap()
+            .daemon();
+
+        assert_eq!(daemon.get_block_count().await.unwrap().get(), 42);
+    }
+
+    #[cfg(feature = "rpc_authentication")]
+    #[tokio::test]
+    async fn http_transport_digest_auth_error() {
+        let transport = HttpTransport::new(reqwest::Client::new()).rpc_authentication(
+            RpcAuthentication::Credentials {
+                username: "user".to_string(),
+                password: "pass".to_string(),
+            },
+        );
+        let call = MethodCall {
+            jsonrpc: Some(Version::V2),
+            method: "get_block_count".to_string(),
+            params: Params::None,
+            id: jsonrpc_core::Id::Num(0),
+        };
+
+        // nothing listens on port 1, the connection is refused
+        let error = transport
+            .json_rpc("http://127.0.0.1:1", &call)
+            .await
+            .unwrap_err();
+        assert!(error.is::<reqwest::Error>());
+        assert_eq!(
+            crate::ErrorClass::of(&error),
+            Some(crate::ErrorClass::Connect)
+        );
+    }
+
+    #[tokio::test]
+    async fn http_transport_batch_rejected() {
+        let server = crate::mock::MockServer::start().await.unwrap();
+        server
+            .json_rpc("get_block_count", json!({ "count": 42, "status": "OK" }))
+            .reject_batches(true);
+
+        let call = |id: &str| MethodCall {
+            jsonrpc: Some(Version::V2),
+            method: "get_block_count".to_string(),
+            params: Params::None,
+            id: jsonrpc_core::Id::Str(id.to_string()),
+        };
+        let error = HttpTransport::new(reqwest::Client::new())
+            .json_rpc_batch(&server.url(), &[call("a"), call("b")])
+            .await
+            .unwrap_err();
+
+        // the calls are left to the client, to be sent under the limits of their methods
+        assert!(error.is::<BatchNotSupported>());
+        assert!(server.requests().is_empty());
+    }
+}
@@ -40,6 +40,16 @@ macro_rules! hash_type_impl {
 
 hash_type_impl!(monero::util::address::PaymentId);
 hash_type_impl!(monero::cryptonote::hash::Hash);
+hash_type_impl!(monero::PublicKey);
+
+impl HashType for monero::util::ringct::Key {
+    fn from_str(v: &str) -> anyhow::Result<Self> {
+        let key: [u8; 32] = hex::decode(v)?
+            .try_into()
+            .map_err(|_| anyhow::Error::msg("Invalid key length"))?;
+        Ok(key.into())
+    }
+}
 
 impl HashType for Vec<u8> {
     fn from_str(v: &str) -> anyhow::Result<Self> {
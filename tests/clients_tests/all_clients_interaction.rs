@@ -3,15 +3,13 @@ use std::collections::HashMap;
 use chrono::DateTime;
 use hex::ToHex;
 use monero::{
-    cryptonote::subaddress::{self, Index},
-    util::address::PaymentId,
-    Address, Amount, Hash, KeyPair, Network, ViewPair,
+    cryptonote::subaddress::Index, util::address::PaymentId, Address, Amount, Hash, Network,
 };
 use monero_rpc::{
     BalanceData, BlockHeightFilter, Destination, GetTransfersCategory, GetTransfersSelector,
     GotTransfer, HashString, IncomingTransfer, IncomingTransfers, KeyImageImportResponse, Payment,
-    PrivateKeyType, SubaddressBalanceData, SweepAllArgs, Transaction, TransactionsResponse,
-    TransferHeight, TransferOptions, TransferPriority, TransferType,
+    PrivateKeyType, SetDaemonOptions, SslSupport, SubaddressBalanceData, SweepAllArgs, Transaction,
+    TransactionsResponse, TransferHeight, TransferOptions, TransferPriority, TransferType,
 };
 
 use super::helpers;
@@ -27,7 +25,8 @@ use super::helpers;
 */
 
 pub async fn run() {
-    let (regtest, daemon_rpc, wallet) = helpers::setup_monero();
+    let (regtest, daemon_rpc, wallet) =
+        helpers::setup_monero_with_fixtures("all_clients_interaction");
 
     // STEP 1: like `basic_wallet`, we start by creating some wallets that will be used later.
 
@@ -39,11 +38,9 @@ pub async fn run() {
     // Such scenario would not happen when running **all** integration tests, since for tests such
     // as `empty_blockchain`, a fresh blockchain is needed every time.
     let wallet_1_full = helpers::wallet::create_wallet_with_empty_password_assert_ok(&wallet).await;
-    let wallet_1_key_pair = KeyPair {
-        view: wallet.query_key(PrivateKeyType::View).await.unwrap(),
-        spend: wallet.query_key(PrivateKeyType::Spend).await.unwrap(),
-    };
-    let wallet_1_address = Address::from_keypair(Network::Mainnet, &wallet_1_key_pair);
+    let wallet_1_key_pair = helpers::wallet::query_key_pair(&wallet).await;
+    let wallet_1_address =
+        helpers::wallet::get_address_assert_key_pair(&wallet, &wallet_1_key_pair).await;
     let (wallet_1_view_only, _) = helpers::wallet::generate_from_keys_assert_ok(
         &wallet,
         monero_rpc::GenerateFromKeysArgs {
@@ -51,8 +48,8 @@ pub async fn run() {
             filename: "".to_string(), // empty, so random name is assigned
             address: wallet_1_address,
             spendkey: None,
-            viewkey: wallet_1_key_pair.view,
-            password: "".to_string(),
+            viewkey: wallet_1_key_pair.view.into(),
+            password: "".into(),
             autosave_current: None,
         },
     )
@@ -111,11 +108,9 @@ pub async fn run() {
     helpers::wallet::open_wallet_with_no_or_empty_password_assert_ok(&wallet, &wallet_2).await;
 
     // query keys of `wallet_2` and get its address
-    let wallet_2_key_pair = KeyPair {
-        view: wallet.query_key(PrivateKeyType::View).await.unwrap(),
-        spend: wallet.query_key(PrivateKeyType::Spend).await.unwrap(),
-    };
-    let wallet_2_address = Address::from_keypair(Network::Mainnet, &wallet_2_key_pair);
+    let wallet_2_key_pair = helpers::wallet::query_key_pair(&wallet).await;
+    let wallet_2_address =
+        helpers::wallet::get_address_assert_key_pair(&wallet, &wallet_2_key_pair).await;
 
     // STEP 3: we test some functions related to a wallet's functionality, such as creating and
     // getting addresses, mining blocks, getting balances, etc. We also test possible scenarios
@@ -123,19 +118,28 @@ pub async fn run() {
 
     // create a subaddress for `wallet_2 and mine a block on the main address and on the
     // subaddress; check the balance at the end
-    let wallet_2_subaddress_1 = subaddress::get_subaddress(
-        &ViewPair::from(&wallet_2_key_pair),
-        Index { major: 0, minor: 1 },
-        Some(Network::Mainnet),
-    );
     let wallet_2_subaddress_1_label = "faaaarcaster".to_string();
-    helpers::wallet::create_address_assert_address_and_address_index(
-        &wallet,
-        0,
-        Some(wallet_2_subaddress_1_label.clone()),
-        (wallet_2_subaddress_1, 1),
-    )
-    .await;
+    let wallet_2_subaddress_1 =
+        match helpers::wallet::get_subaddress(&wallet_2_key_pair, Index { major: 0, minor: 1 }) {
+            Some(subaddress) => {
+                helpers::wallet::create_address_assert_address_and_address_index(
+                    &wallet,
+                    0,
+                    Some(wallet_2_subaddress_1_label.clone()),
+                    (subaddress, 1),
+                )
+                .await
+                .0
+            }
+            // the keys are masked, take the subaddress the wallet reports
+            None => {
+                wallet
+                    .create_address(0, Some(wallet_2_subaddress_1_label.clone()))
+                    .await
+                    .unwrap()
+                    .0
+            }
+        };
 
     let expected_balance = regtest
         .get_block_template(wallet_2_address, 0)
@@ -193,14 +197,24 @@ pub async fn run() {
     .await;
 
     // no error for weird account and address index
-    let wallet_2_subaddress_12345678 = subaddress::get_subaddress(
-        &ViewPair::from(&wallet_2_key_pair),
+    let wallet_2_subaddress_12345678 = match helpers::wallet::get_subaddress(
+        &wallet_2_key_pair,
         Index {
             major: 0,
             minor: 12345678,
         },
-        Some(Network::Mainnet),
-    );
+    ) {
+        Some(subaddress) => subaddress,
+        // the keys are masked, take the subaddress the wallet reports
+        None => {
+            wallet
+                .get_balance(0, Some(vec![12345678]))
+                .await
+                .unwrap()
+                .per_subaddress[0]
+                .address
+        }
+    };
     let expected_balance_data_for_wallet_2_subaddress_12345678 = BalanceData {
         balance: expected_balance,
         unlocked_balance: Amount::from_pico(0),
@@ -326,8 +340,17 @@ pub async fn run() {
     )
     .await;
 
-    // ... restore subaddr_index and send transaction
+    // ... restore subaddr_index, estimate the fee with a dry run, and send transaction
     transfer_options.subaddr_indices = None;
+    helpers::wallet::estimate_tx_size_and_weight_assert_ok(&wallet, 1, 3, Some(16), Some(true))
+        .await;
+    helpers::wallet::transfer_dry_run_assert_ok(
+        &wallet,
+        transfer_1_destination.clone(),
+        transfer_options.clone(),
+        TransferPriority::Default,
+    )
+    .await;
     let transfer_1_data = helpers::wallet::transfer_assert_ok(
         &wallet,
         transfer_1_destination.clone(),
@@ -436,6 +459,12 @@ pub async fn run() {
     )
     .await;
 
+    helpers::daemon_rpc::get_outs_assert_json_and_binary_agree(
+        &daemon_rpc,
+        vec![(Amount::ZERO, 0), (Amount::ZERO, 1)],
+    )
+    .await;
+
     // get_transfer
     let expected_got_transfer = Some(GotTransfer {
         address: wallet_2_address,
@@ -483,7 +512,7 @@ pub async fn run() {
     helpers::wallet::check_tx_key_assert_confirmations_in_pool_status_received_amount(
         &wallet,
         transfer_1_data.tx_hash.0,
-        transfer_1_data.tx_key.0.clone(),
+        transfer_1_data.tx_key.expose_secret().0.clone(),
         wallet_1_address,
         (0, true, transfer_1_destination[&wallet_1_address]),
     )
@@ -491,7 +520,7 @@ pub async fn run() {
     helpers::wallet::check_tx_key_assert_confirmations_in_pool_status_received_amount(
         &wallet,
         transfer_1_data.tx_hash.0,
-        transfer_1_data.tx_key.0.clone(),
+        transfer_1_data.tx_key.expose_secret().0.clone(),
         wallet_2_address,
         // wallet_2 has just one output of value expected_balance;
         // it uses such outout in the transaction
@@ -506,7 +535,7 @@ pub async fn run() {
     helpers::wallet::check_tx_key_error_invalid_txid(
         &wallet,
         Hash::zero(),
-        transfer_1_data.tx_key.0.clone(),
+        transfer_1_data.tx_key.expose_secret().0.clone(),
         wallet_2_address,
     )
     .await;
@@ -520,7 +549,7 @@ pub async fn run() {
     helpers::wallet::check_tx_key_error_invalid_address(
         &wallet,
         transfer_1_data.tx_hash.0,
-        transfer_1_data.tx_key.0.clone(),
+        transfer_1_data.tx_key.expose_secret().0.clone(),
         wallet_3_testnet_address,
     )
     .await;
@@ -663,6 +692,9 @@ pub async fn run() {
             subaddr_index: Index { major: 0, minor: 0 },
             tx_hash: transfer_1_data.tx_hash.clone(),
             block_height: None,
+            frozen: false,
+            unlocked: true,
+            pubkey: String::new(), // any value, since we will not test against it
         }]),
     };
     helpers::wallet::open_wallet_with_no_or_empty_password_assert_ok(&wallet, &wallet_1_view_only)
@@ -685,6 +717,26 @@ pub async fn run() {
     )
     .await;
 
+    // freeze, thaw and frozen on the output received by wallet_1_full
+    let wallet_1_full_key_image = wallet
+        .incoming_transfers(TransferType::All, Some(0), None)
+        .await
+        .unwrap()
+        .transfers
+        .unwrap()[0]
+        .key_image
+        .clone()
+        .unwrap();
+    helpers::wallet::freeze_and_thaw_assert_ok(&wallet, wallet_1_full_key_image).await;
+    helpers::wallet::freeze_error_invalid_key_image(&wallet, "0xgg".to_string()).await;
+
+    // scan_tx, rescan_spent and rescan_blockchain on wallet_1_full; the wallet is refreshed
+    // afterwards so the next steps see the same state as before
+    helpers::wallet::scan_tx_assert_ok(&wallet, vec![transfer_1_data.tx_hash.0]).await;
+    helpers::wallet::rescan_spent_assert_ok(&wallet).await;
+    helpers::wallet::rescan_blockchain_assert_ok(&wallet, None).await;
+    wallet.refresh(None).await.unwrap();
+
     // STEP 6: we create another transfer, but this time from a view-only wallet.
     // Since a view-only wallet cannot sign transactions, we then test signing the transaction
     // created by it using a spend wallet.
@@ -924,4 +976,52 @@ pub async fn run() {
         panic!("No Transfers to Test for");
     }
     //---------------------------------------------------------------------------------------//
+
+    // STEP 8: point the wallet at the daemon requiring authentication, refresh against it with
+    // the right and the wrong credentials, and switch back to the original daemon.
+    helpers::wallet::set_daemon_assert_ok(
+        &wallet,
+        helpers::DAEMON_RPC_AUTHENTICATION_ADDRESS,
+        SetDaemonOptions {
+            trusted: Some(false),
+            ssl_support: Some(SslSupport::Disabled),
+            username: Some("foo".to_string()),
+            password: Some("bar".into()),
+            ..Default::default()
+        },
+    )
+    .await;
+    wallet.refresh(None).await.unwrap();
+
+    // wrong credentials are accepted by `set_daemon`, but the wallet cannot refresh with them
+    helpers::wallet::set_daemon_assert_ok(
+        &wallet,
+        helpers::DAEMON_RPC_AUTHENTICATION_ADDRESS,
+        SetDaemonOptions {
+            trusted: Some(false),
+            ssl_support: Some(SslSupport::Disabled),
+            username: Some("foo".to_string()),
+            password: Some("wrong".into()),
+            ..Default::default()
+        },
+    )
+    .await;
+    helpers::wallet::refresh_error_no_daemon_connection(&wallet).await;
+
+    // the next steps depend on the chain state of the original daemon
+    helpers::wallet::set_daemon_assert_ok(
+        &wallet,
+        helpers::DAEMON_ADDRESS,
+        SetDaemonOptions {
+            trusted: Some(true),
+            ..Default::default()
+        },
+    )
+    .await;
+    wallet.refresh(None).await.unwrap();
+
+    // STEP 9: mine for real, through the daemon and through the wallet, instead of generating
+    // blocks.
+    helpers::daemon_rpc::mining_assert_start_and_stop(&daemon_rpc, wallet_1_address).await;
+    helpers::wallet::mining_assert_start_and_stop(&wallet, &daemon_rpc).await;
 }
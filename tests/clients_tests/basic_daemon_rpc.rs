@@ -8,8 +8,7 @@ use monero_rpc::{HashString, TransactionsResponse};
 * The purpose of this test is to test functions from the `DaemonRpcClient`
 * (i.e, functions from https://www.getmonero.org/resources/developer-guides/daemon-rpc.html#other-daemon-rpc-calls).
 *
-* Currently, there is only one such function: `get_transactions`.
-* The scenarios tested in this test require that **no** transaction between two wallets
+* The scenarios for `get_transactions` tested in this test require that **no** transaction between two wallets
 * have been created. Note that coinbase wallets are insignificant for this test.
 *
 * Scenarios that depend on created transactions between two wallets are tested in
@@ -59,4 +58,9 @@ pub async fn run() {
         expected_transactions_response,
     )
     .await;
+    // network administration, the daemon runs offline
+    helpers::daemon_rpc::get_peer_list_assert_empty(&daemon_rpc).await;
+    helpers::daemon_rpc::in_out_peers_assert_limits(&daemon_rpc).await;
+    helpers::daemon_rpc::set_limit_assert_limits(&daemon_rpc).await;
+    helpers::daemon_rpc::get_net_stats_assert_start_time(&daemon_rpc).await;
 }
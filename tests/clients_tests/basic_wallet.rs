@@ -76,8 +76,8 @@ pub async fn run() {
         filename: "".to_string(), // empty because will be generated by the below function call
         address: Address::from_keypair(Network::Mainnet, &key_pair_1),
         spendkey: None,
-        viewkey: key_pair_1.view,
-        password: "".to_string(),
+        viewkey: key_pair_1.view.into(),
+        password: "".into(),
         autosave_current: None,
     };
     let _ = helpers::wallet::generate_from_keys_assert_ok(&wallet, generate_wallet_args_1.clone())
@@ -94,9 +94,9 @@ pub async fn run() {
         restore_height: Some(0),
         filename: "".to_string(), // empty because will be generated by the below function call
         address: Address::from_keypair(Network::Mainnet, &key_pair_2),
-        spendkey: Some(key_pair_2.spend),
-        viewkey: key_pair_2.view,
-        password: helpers::PWD_1.to_string(),
+        spendkey: Some(key_pair_2.spend.into()),
+        viewkey: key_pair_2.view.into(),
+        password: helpers::PWD_1.into(),
         autosave_current: Some(false),
     };
     let wallet_creation_from_key_pair_2 =
@@ -107,9 +107,9 @@ pub async fn run() {
         restore_height: None,
         filename: wallet_with_empty_pwd,
         address: Address::from_keypair(Network::Mainnet, &key_pair_3),
-        spendkey: Some(key_pair_3.spend),
-        viewkey: key_pair_3.view,
-        password: "".to_string(),
+        spendkey: Some(key_pair_3.spend.into()),
+        viewkey: key_pair_3.view.into(),
+        password: "".into(),
         autosave_current: None,
     };
     helpers::wallet::generate_from_keys_error_filename_already_exists(
@@ -123,9 +123,9 @@ pub async fn run() {
         restore_height: Some(u64::MAX),
         filename: "".to_string(), // empty because will be generated by the below function call
         address: Address::from_keypair(Network::Mainnet, &key_pair_3),
-        spendkey: Some(key_pair_3.spend),
-        viewkey: key_pair_3.view,
-        password: "".to_string(),
+        spendkey: Some(key_pair_3.spend.into()),
+        viewkey: key_pair_3.view.into(),
+        password: "".into(),
         autosave_current: None,
     };
     let _ = helpers::wallet::generate_from_keys_assert_ok(&wallet, generate_wallet_args_3).await;
@@ -135,9 +135,9 @@ pub async fn run() {
         restore_height: None,
         filename: "".to_string(), // empty because will be generated by the below function call
         address: Address::from_keypair(Network::Testnet, &key_pair_3),
-        spendkey: Some(key_pair_3.spend),
-        viewkey: key_pair_3.view,
-        password: "".to_string(),
+        spendkey: Some(key_pair_3.spend.into()),
+        viewkey: key_pair_3.view.into(),
+        password: "".into(),
         autosave_current: None,
     };
     helpers::wallet::generate_from_keys_error_invalid_address(&wallet, generate_wallet_args_3)
@@ -147,7 +147,7 @@ pub async fn run() {
     let restore_wallet_args_1 = RestoreDeterministicWalletArgs {
         restore_height: None,
         filename: "".to_string(), // empty because will be generated by the below function call
-        password: "1234".to_string(),
+        password: "1234".into(),
         autosave_current: None,
         seed: "roster ghost powder afield duplex tiger western adept mailed owed vein dauntless sphere ginger pebbles among moon arbitrary jeopardy voucher noodles casket square tipsy ginger".into(), // Fake data
         seed_offset: None,
@@ -158,7 +158,7 @@ pub async fn run() {
     let restore_wallet_args_2 = RestoreDeterministicWalletArgs {
         restore_height: None,
         filename: "".to_string(), // empty because will be generated by the below function call
-        password: "1234".to_string(),
+        password: "1234".into(),
         autosave_current: None,
         seed: "glare cheese eclipse jeans buffalo velvet trophy lunar petal galaxy frog harmony keyboard".into(), // Fake data
         seed_offset: None,
@@ -219,6 +219,15 @@ pub async fn run() {
     )
     .await;
 
+    for address in [
+        wallet_creation_from_key_pair_1.1.address.to_string(),
+        Address::from_keypair(Network::Testnet, &key_pair_1).to_string(),
+        Address::from_keypair(Network::Stagenet, &key_pair_1).to_string(),
+        "not an address".to_string(),
+    ] {
+        helpers::wallet::validate_address_assert_offline_validation(&wallet, &address).await;
+    }
+
     // open a different wallet for the next few tests
     helpers::wallet::open_wallet_with_password_assert_ok(
         &wallet,
@@ -341,5 +350,29 @@ pub async fn run() {
     .await;
 
     helpers::wallet::create_account_assert_ok(&wallet, Some(String::from("test"))).await;
+
+    // wallet lifecycle: store, change password back and forth, and toggle auto refresh
+    helpers::wallet::store_assert_ok(&wallet).await;
+    helpers::wallet::change_wallet_password_error_invalid_password(
+        &wallet,
+        Some("wrong_password :)".to_string()),
+        Some("new_password".to_string()),
+    )
+    .await;
+    helpers::wallet::change_wallet_password_assert_ok(
+        &wallet,
+        Some(helpers::PWD_1.to_string()),
+        Some("new_password".to_string()),
+    )
+    .await;
+    helpers::wallet::change_wallet_password_assert_ok(
+        &wallet,
+        Some("new_password".to_string()),
+        Some(helpers::PWD_1.to_string()),
+    )
+    .await;
+    helpers::wallet::auto_refresh_assert_ok(&wallet, Some(false), None).await;
+    helpers::wallet::auto_refresh_assert_ok(&wallet, Some(true), Some(20)).await;
+
     helpers::wallet::close_wallet_assert_ok(&wallet).await;
 }
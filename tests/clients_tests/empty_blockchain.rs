@@ -1,5 +1,7 @@
+use std::str::FromStr;
+
 use chrono::DateTime;
-use monero::{Address, Amount, Network};
+use monero::{cryptonote::hash::Hash, Address, Amount, Network};
 use monero_rpc::{BlockHash, BlockHeaderResponse, BlockTemplate, HashString};
 
 use super::helpers;
@@ -37,9 +39,14 @@ pub async fn run() {
             difficulty: 1,
             expected_reward: Amount::from_pico(35184338534400),
             height: 1,
+            next_seed_hash: None,
             prev_hash: HashString(genesis_block_hash),
             reserved_offset: 185, // may very, so not used inside the test
+            // the RandomX key is the hash of the genesis block until the first seed epoch
+            seed_hash: HashString(Hash::from_slice(genesis_block_hash.as_bytes())),
+            seed_height: 0,
             untrusted: false,
+            wide_difficulty: 1,
         },
     )
     .await;
@@ -48,21 +55,30 @@ pub async fn run() {
 
     let genesis_block_header = BlockHeaderResponse {
         block_size: 80,
+        block_weight: 80,
+        cumulative_difficulty: 1,
         depth: 0,
         difficulty: 1,
         hash: genesis_block_hash,
         height: 0,
+        long_term_weight: 80,
         major_version: 1,
+        miner_tx_hash: Hash::from_str(
+            "c88ce9783b4f11190d7b9c17a69c1c52200f9faaee8e98dd07e6811175177139",
+        )
+        .unwrap(),
         minor_version: 0,
         nonce: 10000,
         num_txes: 0,
         orphan_status: false,
+        pow_hash: None,
         prev_hash: BlockHash::zero(),
         reward: Amount::from_pico(17592186044415),
         // this **is** used inside the test functions, since this block header corresponds
         // to the genesis block;
         // note that in the `non_empty_blockchain`, this field is **not** tested.
         timestamp: DateTime::from_timestamp(0, 0).unwrap(),
+        wide_difficulty: 1,
     };
 
     helpers::regtest::get_last_block_header_assert_block_header(
@@ -114,4 +130,10 @@ pub async fn run() {
     // `end_height` params, and the RPC should then return an error.
     #[allow(clippy::reversed_empty_ranges)]
     helpers::regtest::get_block_headers_range_error(&regtest, 4..=0).await;
+
+    // network administration, the daemon runs offline
+    helpers::regtest::get_connections_assert_no_connection(&regtest).await;
+    helpers::regtest::set_bans_assert_ban_and_unban(&regtest, "10.11.12.13").await;
+    helpers::regtest::sync_info_assert_synced(&regtest, 1).await;
+    helpers::regtest::hard_fork_info_assert_enabled(&regtest).await;
 }
@@ -1,5 +1,5 @@
-use monero::cryptonote::hash::Hash;
-use monero_rpc::{DaemonRpcClient, TransactionsResponse};
+use monero::{cryptonote::hash::Hash, Address, Amount};
+use monero_rpc::{DaemonRpcClient, PeerList, TransactionsResponse};
 
 pub async fn get_transactions_assert_response(
     daemon_rpc: &DaemonRpcClient,
@@ -93,3 +93,66 @@ pub async fn get_transactions_as_json_pruned_assert_response_not_empty(
         .unwrap();
     test_tx_json_not_empty(transactions_response);
 }
+
+pub async fn get_peer_list_assert_empty(daemon_rpc: &DaemonRpcClient) {
+    // the daemon runs offline
+    let peer_list = daemon_rpc.get_peer_list(None).await.unwrap();
+    assert_eq!(peer_list, PeerList::default());
+}
+
+pub async fn in_out_peers_assert_limits(daemon_rpc: &DaemonRpcClient) {
+    assert_eq!(daemon_rpc.in_peers(Some(8)).await.unwrap(), 8);
+    assert_eq!(daemon_rpc.in_peers(None).await.unwrap(), 8);
+    assert_eq!(daemon_rpc.out_peers(Some(4)).await.unwrap(), 4);
+    assert_eq!(daemon_rpc.out_peers(None).await.unwrap(), 4);
+}
+
+pub async fn set_limit_assert_limits(daemon_rpc: &DaemonRpcClient) {
+    let limits = daemon_rpc.set_limit(1024, 512).await.unwrap();
+    assert_eq!((limits.limit_down, limits.limit_up), (1024, 512));
+    assert_eq!(daemon_rpc.get_limit().await.unwrap(), limits);
+
+    // 0 leaves a limit unchanged
+    let limits = daemon_rpc.set_limit(0, 256).await.unwrap();
+    assert_eq!((limits.limit_down, limits.limit_up), (1024, 256));
+
+    // -1 resets the limits to their defaults
+    let limits = daemon_rpc.set_limit(-1, -1).await.unwrap();
+    assert_ne!((limits.limit_down, limits.limit_up), (1024, 256));
+}
+
+pub async fn get_net_stats_assert_start_time(daemon_rpc: &DaemonRpcClient) {
+    let net_stats = daemon_rpc.get_net_stats().await.unwrap();
+    assert!(net_stats.start_time > 0);
+}
+
+pub async fn get_outs_assert_json_and_binary_agree(
+    daemon_rpc: &DaemonRpcClient,
+    outputs: Vec<(Amount, u64)>,
+) {
+    let outs = daemon_rpc.get_outs(outputs.clone()).await.unwrap();
+    assert_eq!(outs.len(), outputs.len());
+    // the outputs of amount zero are RingCT coinbase outputs, none of them in the genesis block
+    assert!(outs.iter().all(|out| out.height > 0));
+    assert!(outs.iter().all(|out| out.txid != Hash::zero()));
+
+    let outs_bin = daemon_rpc.get_outs_bin(outputs).await.unwrap();
+    assert_eq!(outs_bin, outs);
+}
+
+pub async fn mining_assert_start_and_stop(daemon_rpc: &DaemonRpcClient, address: Address) {
+    daemon_rpc
+        .start_mining(address, 1, false, true)
+        .await
+        .unwrap();
+    let mining_status = daemon_rpc.mining_status().await.unwrap();
+    assert!(mining_status.active);
+    assert_eq!(mining_status.address, Some(address));
+    assert_eq!(mining_status.threads_count, 1);
+    assert!(!mining_status.is_background_mining_enabled);
+
+    daemon_rpc.stop_mining().await.unwrap();
+    let mining_status = daemon_rpc.mining_status().await.unwrap();
+    assert!(!mining_status.active);
+    assert_eq!(mining_status.address, None);
+}
@@ -1,6 +1,10 @@
 use monero::{KeyPair, PrivateKey};
-use monero_rpc::{BlockHash, RpcClientBuilder};
-use std::{env, str::FromStr};
+use monero_rpc::{BlockHash, ReplayTransport, RpcClient, RpcClientBuilder};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 pub mod daemon_rpc;
 pub mod regtest;
@@ -8,6 +12,11 @@ pub mod wallet;
 
 pub const PWD_1: &str = "pwd_farcaster";
 
+// Addresses of the daemons as seen from the wallet RPC, i.e. inside the docker-compose network
+// defined in tests/docker-compose.yml.
+pub const DAEMON_ADDRESS: &str = "monerod:18081";
+pub const DAEMON_RPC_AUTHENTICATION_ADDRESS: &str = "monerod-rpc-authentication:18081";
+
 pub fn setup_monero() -> (
     monero_rpc::RegtestDaemonJsonRpcClient,
     monero_rpc::DaemonRpcClient,
@@ -35,6 +44,74 @@ pub fn setup_monero() -> (
     (regtest, daemon_rpc, wallet)
 }
 
+/// Like [`setup_monero`], but records the traffic of each client to
+/// `$MONERO_RPC_RECORD_DIR/{scenario}_{client}.jsonl` if set, or replays it from
+/// `$MONERO_RPC_REPLAY_DIR` if set, so that the scenario can run without docker.
+pub fn setup_monero_with_fixtures(
+    scenario: &str,
+) -> (
+    monero_rpc::RegtestDaemonJsonRpcClient,
+    monero_rpc::DaemonRpcClient,
+    monero_rpc::WalletClient,
+) {
+    if let Ok(dir) = env::var("MONERO_RPC_REPLAY_DIR") {
+        return replay_monero(scenario, Path::new(&dir));
+    }
+    let record_dir = env::var("MONERO_RPC_RECORD_DIR").ok();
+    setup_clients(|client, addr| {
+        let mut builder = RpcClientBuilder::new();
+        if let Some(dir) = &record_dir {
+            builder = builder.record_to(fixture(Path::new(dir), scenario, client));
+        }
+        builder.build(addr).unwrap()
+    })
+}
+
+/// Replay the traffic of a scenario recorded by [`setup_monero_with_fixtures`] in `dir`.
+///
+/// Replay is lenient because scenarios use random wallet names.
+pub fn replay_monero(
+    scenario: &str,
+    dir: &Path,
+) -> (
+    monero_rpc::RegtestDaemonJsonRpcClient,
+    monero_rpc::DaemonRpcClient,
+    monero_rpc::WalletClient,
+) {
+    setup_clients(|client, addr| {
+        let transport = ReplayTransport::from_file(fixture(dir, scenario, client))
+            .unwrap()
+            .lenient(true);
+        RpcClientBuilder::new()
+            .transport(transport)
+            .build(addr)
+            .unwrap()
+    })
+}
+
+fn fixture(dir: &Path, scenario: &str, client: &str) -> PathBuf {
+    dir.join(format!("{}_{}.jsonl", scenario, client))
+}
+
+fn setup_clients(
+    build: impl Fn(&str, String) -> RpcClient,
+) -> (
+    monero_rpc::RegtestDaemonJsonRpcClient,
+    monero_rpc::DaemonRpcClient,
+    monero_rpc::WalletClient,
+) {
+    let dhost = env::var("MONERO_DAEMON_HOST").unwrap_or_else(|_| "localhost".into());
+    let whost = env::var("MONERO_WALLET_HOST_1").unwrap_or_else(|_| "localhost".into());
+
+    let regtest = build("daemon", format!("http://{}:18081", dhost))
+        .daemon()
+        .regtest();
+    let daemon_rpc = build("daemon_rpc", format!("http://{}:18081", dhost)).daemon_rpc();
+    let wallet = build("wallet", format!("http://{}:18083", whost)).wallet();
+
+    (regtest, daemon_rpc, wallet)
+}
+
 pub fn get_keypair_1() -> KeyPair {
     KeyPair {
         view: PrivateKey::from_str(
@@ -1,10 +1,10 @@
-use std::ops::RangeInclusive;
+use std::{ops::RangeInclusive, time::Duration};
 
 use chrono::{DateTime, NaiveDate, Utc};
-use monero::{Address, Network};
+use monero::{cryptonote::hash::Hash, Address, Amount, Network};
 use monero_rpc::{
-    BlockHash, BlockHeaderResponse, BlockTemplate, GenerateBlocksResponse, HashString,
-    RegtestDaemonJsonRpcClient,
+    AuxPow, BlockHash, BlockHeaderResponse, BlockTemplate, GenerateBlocksResponse, HashString,
+    OutputDistributionArgs, OutputHistogramArgs, RegtestDaemonJsonRpcClient, SetBanArgs,
 };
 use serde::Deserialize;
 
@@ -230,17 +230,20 @@ fn test_get_block_header_assert_block_header(
     expected_block_header: BlockHeaderResponse,
 ) {
     #[derive(Debug, PartialEq, Deserialize)]
-    // `block_size` is not tested because it varies
+    // `block_size`, `block_weight` and `long_term_weight` are not tested because they vary
     struct Helper {
+        cumulative_difficulty: u128,
         depth: u64,
         difficulty: u64,
         hash: BlockHash,
         height: u64,
+        miner_tx_hash: Hash,
         nonce: u32,
         num_txes: u64,
         orphan_status: bool,
         prev_hash: BlockHash,
         reward: u64,
+        wide_difficulty: u128,
     }
 
     if block_header.height == 0 {
@@ -341,6 +344,35 @@ pub async fn get_block_headers_range_assert_block_headers(
     assert_eq!(block_headers, expected_block_headers);
 }
 
+pub async fn batch_get_block_headers_assert_block_headers(
+    regtest: &RegtestDaemonJsonRpcClient,
+    range: RangeInclusive<u64>,
+    expected_block_headers: Vec<BlockHeaderResponse>,
+    current_top_block_height: u64,
+) {
+    let mut batch = regtest.batch();
+    let entries = range
+        .map(|height| batch.get_block_header(monero_rpc::GetBlockHeaderSelector::Height(height)))
+        .collect::<Vec<_>>();
+    let too_high = batch.get_block_header(monero_rpc::GetBlockHeaderSelector::Height(
+        current_top_block_height + 1,
+    ));
+
+    let mut results = batch.send().await.unwrap();
+    let block_headers = entries
+        .into_iter()
+        .map(|entry| results.take(entry).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(block_headers, expected_block_headers);
+    assert_eq!(
+        results.take(too_high).unwrap_err().to_string(),
+        format!(
+            "Server error: Requested block height: {} greater than current top block height: {current_top_block_height}",
+            current_top_block_height + 1
+        )
+    );
+}
+
 pub async fn get_block_headers_range_error(
     regtest: &RegtestDaemonJsonRpcClient,
     range: RangeInclusive<u64>,
@@ -351,3 +383,233 @@ pub async fn get_block_headers_range_error(
         "Server error: Invalid start/end heights."
     );
 }
+
+pub async fn get_connections_assert_no_connection(regtest: &RegtestDaemonJsonRpcClient) {
+    // the daemon runs offline
+    let connections = regtest.get_connections().await.unwrap();
+    assert!(connections.is_empty());
+}
+
+pub async fn set_bans_assert_ban_and_unban(regtest: &RegtestDaemonJsonRpcClient, host: &str) {
+    assert_eq!(regtest.banned(host.to_string()).await.unwrap(), None);
+
+    regtest
+        .set_bans(vec![SetBanArgs {
+            host: host.to_string(),
+            ban: true,
+            seconds: 3600,
+        }])
+        .await
+        .unwrap();
+    let bans = regtest.get_bans().await.unwrap();
+    let ban = bans.iter().find(|ban| ban.host == host).unwrap();
+    assert!(ban.seconds > 0 && ban.seconds <= 3600);
+    let seconds = regtest.banned(host.to_string()).await.unwrap().unwrap();
+    assert!(seconds > 0 && seconds <= 3600);
+
+    regtest
+        .set_bans(vec![SetBanArgs {
+            host: host.to_string(),
+            ban: false,
+            seconds: 0,
+        }])
+        .await
+        .unwrap();
+    let bans = regtest.get_bans().await.unwrap();
+    assert!(bans.iter().all(|ban| ban.host != host));
+    assert_eq!(regtest.banned(host.to_string()).await.unwrap(), None);
+}
+
+pub async fn sync_info_assert_synced(regtest: &RegtestDaemonJsonRpcClient, expected_height: u64) {
+    // the daemon runs offline, so no peer advertises a target height
+    let sync_info = regtest.sync_info().await.unwrap();
+    assert_eq!(sync_info.height, expected_height);
+    assert_eq!(sync_info.target_height, 0);
+    assert!(sync_info.peers.is_empty());
+    assert!(sync_info.spans.is_empty());
+    assert!(sync_info.is_synced());
+
+    let wait = regtest.wait_until_synced(Duration::from_millis(100), |_| ());
+    let sync_info = tokio::time::timeout(Duration::from_secs(5), wait)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sync_info.height, expected_height);
+}
+
+pub async fn hard_fork_info_assert_enabled(regtest: &RegtestDaemonJsonRpcClient) {
+    let last_block_header = regtest
+        .get_block_header(monero_rpc::GetBlockHeaderSelector::Last)
+        .await
+        .unwrap();
+
+    let hard_fork_info = regtest.hard_fork_info(None).await.unwrap();
+    assert!(hard_fork_info.enabled);
+    assert_eq!(
+        u64::from(hard_fork_info.version),
+        last_block_header.major_version
+    );
+
+    let hard_fork_info = regtest.hard_fork_info(Some(1)).await.unwrap();
+    assert!(hard_fork_info.enabled);
+    assert_eq!(hard_fork_info.earliest_height, 0);
+}
+
+pub async fn get_coinbase_tx_sum_assert_sum_of_rewards(
+    regtest: &RegtestDaemonJsonRpcClient,
+    range: RangeInclusive<u64>,
+) {
+    let (block_headers, _) = regtest
+        .get_block_headers_range(range.clone())
+        .await
+        .unwrap();
+    let rewards: u64 = block_headers.iter().map(|h| h.reward.as_pico()).sum();
+
+    let count = range.end() - range.start() + 1;
+    let sum = regtest
+        .get_coinbase_tx_sum(*range.start(), count)
+        .await
+        .unwrap();
+    // blocks generated in regtest hold no transaction, hence no fee
+    assert_eq!(sum.emission_amount, Amount::from_pico(rewards));
+    assert_eq!(sum.wide_emission_amount, u128::from(rewards));
+    assert_eq!(sum.fee_amount, Amount::ZERO);
+    assert_eq!(sum.wide_fee_amount, 0);
+}
+
+pub async fn get_output_histogram_assert_coinbase_outputs(
+    regtest: &RegtestDaemonJsonRpcClient,
+    expected_count: u64,
+) {
+    let histogram = regtest
+        .get_output_histogram(OutputHistogramArgs {
+            amounts: vec![Amount::ZERO],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(histogram.len(), 1);
+    assert_eq!(histogram[0].amount, Amount::ZERO);
+    assert_eq!(histogram[0].total_instances, expected_count);
+}
+
+pub async fn get_output_distribution_assert_one_output_per_block(
+    regtest: &RegtestDaemonJsonRpcClient,
+    range: RangeInclusive<u64>,
+) {
+    let count = (range.end() - range.start() + 1) as usize;
+
+    for (binary, compress) in [(false, false), (true, false), (true, true)] {
+        let distributions = regtest
+            .get_output_distribution(OutputDistributionArgs {
+                amounts: vec![Amount::ZERO],
+                from_height: Some(*range.start()),
+                to_height: Some(*range.end()),
+                cumulative: false,
+                binary,
+                compress,
+            })
+            .await
+            .unwrap();
+        assert_eq!(distributions.len(), 1);
+        assert_eq!(distributions[0].start_height, *range.start());
+        // each block holds a single RingCT coinbase output
+        assert_eq!(distributions[0].distribution, vec![1; count]);
+    }
+}
+
+pub async fn get_miner_data_assert_tip(regtest: &RegtestDaemonJsonRpcClient, address: Address) {
+    let last_block_header = regtest
+        .get_block_header(monero_rpc::GetBlockHeaderSelector::Last)
+        .await
+        .unwrap();
+    let block_template = regtest.get_block_template(address, 0).await.unwrap();
+
+    let miner_data = regtest.get_miner_data().await.unwrap();
+    assert_eq!(miner_data.height, last_block_header.height + 1);
+    assert_eq!(miner_data.prev_id.0, last_block_header.hash);
+    assert_eq!(miner_data.seed_hash, block_template.seed_hash);
+    assert_eq!(miner_data.difficulty, 1);
+    assert!(miner_data.tx_backlog.is_empty());
+}
+
+pub async fn calc_pow_assert_hash(regtest: &RegtestDaemonJsonRpcClient, address: Address) {
+    let miner_data = regtest.get_miner_data().await.unwrap();
+    let block_template = regtest.get_block_template(address, 0).await.unwrap();
+
+    let calc_pow = |blob: Vec<u8>| {
+        regtest.calc_pow(
+            miner_data.major_version,
+            block_template.height,
+            blob,
+            block_template.seed_hash.0,
+        )
+    };
+    let pow_hash = calc_pow(block_template.blockhashing_blob.0.clone())
+        .await
+        .unwrap();
+    assert_ne!(pow_hash, Hash::zero());
+    assert_eq!(
+        calc_pow(block_template.blockhashing_blob.0.clone())
+            .await
+            .unwrap(),
+        pow_hash
+    );
+
+    // the nonce is right after the major and minor versions, the timestamp and the previous
+    // block hash
+    let mut blob = block_template.blockhashing_blob.0;
+    blob[39] ^= 1;
+    assert_ne!(calc_pow(blob).await.unwrap(), pow_hash);
+}
+
+pub async fn add_aux_pow_assert_merkle_root(
+    regtest: &RegtestDaemonJsonRpcClient,
+    address: Address,
+) {
+    let block_template = regtest.get_block_template(address, 0).await.unwrap();
+    let aux_pow = vec![AuxPow {
+        id: HashString(Hash::from_slice(&[1; 32])),
+        hash: HashString(Hash::from_slice(&[2; 32])),
+    }];
+
+    let aux_template = regtest
+        .add_aux_pow(block_template.blocktemplate_blob.0.clone(), aux_pow.clone())
+        .await
+        .unwrap();
+    // a single chain is the root of its merkle tree
+    assert_eq!(aux_template.merkle_tree_depth, 0);
+    assert_eq!(aux_template.merkle_root, aux_pow[0].hash);
+    assert_eq!(aux_template.aux_pow, aux_pow);
+    assert_ne!(
+        aux_template.blocktemplate_blob,
+        block_template.blocktemplate_blob
+    );
+}
+
+pub async fn submit_block_assert_mined_with_template_helpers(
+    regtest: &RegtestDaemonJsonRpcClient,
+    address: Address,
+) {
+    let mut block_template = regtest.get_block_template(address, 8).await.unwrap();
+    assert_eq!(block_template.reserved_size(), 8);
+    assert_eq!(
+        block_template.hashing_blob().unwrap(),
+        block_template.blockhashing_blob.0
+    );
+
+    block_template.set_extra_nonce(&[1, 2, 3, 4]).unwrap();
+    block_template.set_nonce(42).unwrap();
+    // the difficulty is fixed to 1, any nonce is a valid proof of work
+    regtest
+        .submit_block(block_template.submit_blob())
+        .await
+        .unwrap();
+
+    let last_block_header = regtest
+        .get_block_header(monero_rpc::GetBlockHeaderSelector::Last)
+        .await
+        .unwrap();
+    assert_eq!(last_block_header.height, block_template.height);
+    assert_eq!(last_block_header.nonce, 42);
+}
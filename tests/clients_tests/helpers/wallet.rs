@@ -2,12 +2,15 @@ use std::collections::HashMap;
 use std::ops::Range;
 
 use monero::{
-    cryptonote::subaddress::Index, util::address::PaymentId, Address, Amount, Hash, PrivateKey,
+    cryptonote::subaddress::{self, Index},
+    util::address::PaymentId,
+    Address, Amount, Hash, KeyPair, Network, PrivateKey, ViewPair,
 };
 use monero_rpc::{
-    AddressData, BalanceData, GenerateFromKeysArgs, GetAccountsData, GetTransfersCategory,
-    GetTransfersSelector, GotTransfer, HashString, IncomingTransfers, KeyImageImportResponse,
-    Payment, PrivateKeyType, RestoreDeterministicWalletArgs, SignedKeyImage, SignedTransferOutput,
+    AddressData, AddressValidation, BalanceData, DaemonRpcClient, GenerateFromKeysArgs,
+    GetAccountsData, GetTransfersCategory, GetTransfersSelector, GotTransfer, HashString,
+    IncomingTransfers, KeyImageImportResponse, Payment, PrivateKeyType,
+    RestoreDeterministicWalletArgs, Secret, SetDaemonOptions, SignedKeyImage, SignedTransferOutput,
     SweepAllArgs, TransferData, TransferOptions, TransferPriority, TransferType, WalletClient,
     WalletCreation, WalletRestoration,
 };
@@ -44,7 +47,7 @@ async fn create_wallet(
     let wallet_name: String = get_random_name();
 
     wallet
-        .create_wallet(wallet_name.clone(), password, language)
+        .create_wallet(wallet_name.clone(), password.map(Secret::new), language)
         .await
         .map(|_| wallet_name)
 }
@@ -100,13 +103,40 @@ pub async fn close_wallet_error_no_wallet_file(wallet: &WalletClient) {
     assert_eq!(err.to_string(), "Server error: No wallet file");
 }
 
+pub async fn store_assert_ok(wallet: &WalletClient) {
+    wallet.store().await.unwrap();
+}
+
+pub async fn change_wallet_password_assert_ok(
+    wallet: &WalletClient,
+    old_password: Option<String>,
+    new_password: Option<String>,
+) {
+    wallet
+        .change_wallet_password(old_password.map(Secret::new), new_password.map(Secret::new))
+        .await
+        .unwrap();
+}
+
+pub async fn change_wallet_password_error_invalid_password(
+    wallet: &WalletClient,
+    old_password: Option<String>,
+    new_password: Option<String>,
+) {
+    let err = wallet
+        .change_wallet_password(old_password.map(Secret::new), new_password.map(Secret::new))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Server error: Invalid original password.");
+}
+
 pub async fn open_wallet_with_password_assert_ok(
     wallet: &WalletClient,
     filename: &str,
     password: &str,
 ) {
     wallet
-        .open_wallet(filename.to_string(), Some(password.to_string()))
+        .open_wallet(filename.to_string(), Some(password.into()))
         .await
         .unwrap();
 }
@@ -117,7 +147,7 @@ pub async fn open_wallet_with_no_or_empty_password_assert_ok(
 ) {
     // if wallet has no password, both calls should work
     wallet
-        .open_wallet(filename.to_string(), Some("".to_string()))
+        .open_wallet(filename.to_string(), Some("".into()))
         .await
         .unwrap();
     wallet
@@ -151,7 +181,7 @@ pub async fn open_wallet_error_wrong_password(
 ) {
     let version = wallet.get_version().await.unwrap();
     let err = wallet
-        .open_wallet(filename.to_string(), password)
+        .open_wallet(filename.to_string(), password.map(Secret::new))
         .await
         .unwrap_err();
     let expected_error_message = if version.1 < WALLET_VERSION_0_18_4_0 {
@@ -300,6 +330,15 @@ pub async fn get_address_index_error_invalid_address(wallet: &WalletClient, addr
     assert_eq!(index_err.to_string(), "Server error: Invalid address");
 }
 
+pub async fn validate_address_assert_offline_validation(wallet: &WalletClient, address: &str) {
+    // the wallet runs in regtest, so only `any_net_type` validates addresses
+    let validation = wallet
+        .validate_address(address.to_string(), Some(true), None)
+        .await
+        .unwrap();
+    assert_eq!(validation, AddressValidation::offline(address, None));
+}
+
 pub async fn create_address_assert_address_and_address_index(
     wallet: &WalletClient,
     account_index: u32,
@@ -379,18 +418,86 @@ pub async fn refresh_assert_received_money(
     assert_eq!(res.received_money, expected_received_money);
 }
 
+pub async fn auto_refresh_assert_ok(
+    wallet: &WalletClient,
+    enable: Option<bool>,
+    period: Option<u32>,
+) {
+    wallet.auto_refresh(enable, period).await.unwrap();
+}
+
+pub async fn rescan_blockchain_assert_ok(wallet: &WalletClient, hard: Option<bool>) {
+    wallet.rescan_blockchain(hard).await.unwrap();
+}
+
+pub async fn rescan_spent_assert_ok(wallet: &WalletClient) {
+    wallet.rescan_spent().await.unwrap();
+}
+
+pub async fn scan_tx_assert_ok(wallet: &WalletClient, txids: Vec<Hash>) {
+    wallet.scan_tx(txids).await.unwrap();
+}
+
+pub async fn set_daemon_assert_ok(wallet: &WalletClient, address: &str, options: SetDaemonOptions) {
+    wallet
+        .set_daemon(address.to_string(), options)
+        .await
+        .unwrap();
+}
+
 pub async fn refresh_error(wallet: &WalletClient) {
     let err = wallet.refresh(None).await.unwrap_err();
     assert_eq!(err.to_string(), "Server error: No wallet file");
 }
 
+pub async fn refresh_error_no_daemon_connection(wallet: &WalletClient) {
+    let err = wallet.refresh(None).await.unwrap_err();
+    assert!(err.to_string().contains("no connection to daemon"));
+}
+
 pub async fn query_key_assert_key(
     wallet: &WalletClient,
     key_type: PrivateKeyType,
     expected_key: PrivateKey,
 ) {
     let key = wallet.query_key(key_type).await.unwrap();
-    assert_eq!(key, expected_key);
+    assert_eq!(key.expose_secret(), &expected_key);
+}
+
+/// Keys recorded in fixtures are zeroed, see [`monero_rpc::RecordingTransport`], so addresses
+/// cannot be derived from them when the scenario is replayed.
+pub fn is_masked(key_pair: &KeyPair) -> bool {
+    key_pair.view.as_bytes() == [0; 32]
+}
+
+pub async fn query_key_pair(wallet: &WalletClient) -> KeyPair {
+    KeyPair {
+        view: *wallet
+            .query_key(PrivateKeyType::View)
+            .await
+            .unwrap()
+            .expose_secret(),
+        spend: *wallet
+            .query_key(PrivateKeyType::Spend)
+            .await
+            .unwrap()
+            .expose_secret(),
+    }
+}
+
+pub async fn get_address_assert_key_pair(wallet: &WalletClient, key_pair: &KeyPair) -> Address {
+    let address = wallet.get_address(0, None).await.unwrap().address;
+    if !is_masked(key_pair) {
+        assert_eq!(address, Address::from_keypair(Network::Mainnet, key_pair));
+    }
+    address
+}
+
+/// The subaddress of the wallet with `key_pair` at `index`, unless its keys are masked.
+pub fn get_subaddress(key_pair: &KeyPair, index: Index) -> Option<Address> {
+    (!is_masked(key_pair)).then(|| {
+        subaddress::get_subaddress(&ViewPair::from(key_pair), index, Some(Network::Mainnet))
+    })
 }
 
 pub async fn query_key_error_query_spend_key_for_view_only_wallet(wallet: &WalletClient) {
@@ -430,6 +537,39 @@ pub async fn transfer_assert_ok(
     t
 }
 
+pub async fn transfer_dry_run_assert_ok(
+    wallet: &WalletClient,
+    destinations: HashMap<Address, Amount>,
+    options: TransferOptions,
+    priority: TransferPriority,
+) -> TransferData {
+    let t = wallet
+        .transfer_dry_run(destinations.clone(), priority, options)
+        .await
+        .unwrap();
+    let dest_amount = Amount::from_pico(destinations.into_values().map(|a| a.as_pico()).sum());
+    assert_eq!(t.amount, dest_amount);
+    assert!(t.fee > Amount::from_pico(0));
+    assert!(t.weight > 0);
+    assert!(!t.tx_metadata.0.is_empty());
+    t
+}
+
+pub async fn estimate_tx_size_and_weight_assert_ok(
+    wallet: &WalletClient,
+    n_inputs: u32,
+    n_outputs: u32,
+    ring_size: Option<u32>,
+    rct: Option<bool>,
+) {
+    let estimate = wallet
+        .estimate_tx_size_and_weight(n_inputs, n_outputs, ring_size, rct)
+        .await
+        .unwrap();
+    assert!(estimate.size > 0);
+    assert!(estimate.weight >= estimate.size);
+}
+
 pub async fn transfer_error_invalid_balance(
     wallet: &WalletClient,
     destinations: HashMap<Address, Amount>,
@@ -534,7 +674,10 @@ pub async fn check_tx_key_assert_confirmations_in_pool_status_received_amount(
     address: Address,
     expected_confirmations_and_in_pool_status_and_received_amount: (u64, bool, Amount),
 ) {
-    let res = wallet.check_tx_key(txid, tx_key, address).await.unwrap();
+    let res = wallet
+        .check_tx_key(txid, Secret::new(tx_key), address)
+        .await
+        .unwrap();
     assert_eq!(
         res,
         expected_confirmations_and_in_pool_status_and_received_amount
@@ -548,7 +691,7 @@ pub async fn check_tx_key_error_invalid_txid(
     address: Address,
 ) {
     let err = wallet
-        .check_tx_key(txid, tx_key, address)
+        .check_tx_key(txid, Secret::new(tx_key), address)
         .await
         .unwrap_err();
     assert_eq!(
@@ -564,7 +707,7 @@ pub async fn check_tx_key_error_invalid_tx_key(
     address: Address,
 ) {
     let err = wallet
-        .check_tx_key(txid, tx_key, address)
+        .check_tx_key(txid, Secret::new(tx_key), address)
         .await
         .unwrap_err();
     assert_eq!(err.to_string(), "Server error: Tx key has invalid format");
@@ -577,7 +720,7 @@ pub async fn check_tx_key_error_invalid_address(
     address: Address,
 ) {
     let err = wallet
-        .check_tx_key(txid, tx_key, address)
+        .check_tx_key(txid, Secret::new(tx_key), address)
         .await
         .unwrap_err();
     assert_eq!(err.to_string(), "Server error: Invalid address");
@@ -643,7 +786,7 @@ pub async fn incoming_transfers_assert_incoming_transfers(
         .unwrap();
 
     // we will not test against
-    // `global_index` nor `tx_size` nor `key_image` nor `block_height`
+    // `global_index` nor `tx_size` nor `key_image` nor `block_height` nor `pubkey`
     if let Some(ref transfers) = incoming_transfers.transfers {
         expected_incoming_transfers
             .transfers
@@ -656,12 +799,31 @@ pub async fn incoming_transfers_assert_incoming_transfers(
                 t.tx_size = transfers[i].tx_size;
                 t.key_image = transfers[i].key_image.clone();
                 t.block_height = transfers[i].block_height.clone();
+                t.pubkey = transfers[i].pubkey.clone();
             });
     }
 
     assert_eq!(incoming_transfers, expected_incoming_transfers);
 }
 
+pub async fn freeze_and_thaw_assert_ok(wallet: &WalletClient, key_image: String) {
+    frozen_assert_frozen(wallet, key_image.clone(), false).await;
+    wallet.freeze(key_image.clone()).await.unwrap();
+    frozen_assert_frozen(wallet, key_image.clone(), true).await;
+    wallet.thaw(key_image.clone()).await.unwrap();
+    frozen_assert_frozen(wallet, key_image, false).await;
+}
+
+pub async fn frozen_assert_frozen(wallet: &WalletClient, key_image: String, expected_frozen: bool) {
+    let frozen = wallet.frozen(key_image).await.unwrap();
+    assert_eq!(frozen, expected_frozen);
+}
+
+pub async fn freeze_error_invalid_key_image(wallet: &WalletClient, key_image: String) {
+    let err = wallet.freeze(key_image).await.unwrap_err();
+    assert_eq!(err.to_string(), "Server error: failed to parse key image");
+}
+
 pub async fn sign_transfer_assert_ok(
     wallet: &WalletClient,
     unsigned_txset: Vec<u8>,
@@ -831,3 +993,16 @@ pub async fn create_check_tx_proof_assert_ok(
     assert!(check_res.is_ok());
     assert!(check_res.unwrap().good);
 }
+
+pub async fn mining_assert_start_and_stop(wallet: &WalletClient, daemon_rpc: &DaemonRpcClient) {
+    let address = wallet.get_address(0, None).await.unwrap().address;
+
+    wallet.start_mining(1, false, true).await.unwrap();
+    let mining_status = daemon_rpc.mining_status().await.unwrap();
+    assert!(mining_status.active);
+    assert_eq!(mining_status.address, Some(address));
+
+    wallet.stop_mining().await.unwrap();
+    let mining_status = daemon_rpc.mining_status().await.unwrap();
+    assert!(!mining_status.active);
+}
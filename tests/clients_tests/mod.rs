@@ -5,3 +5,4 @@ pub mod basic_daemon_rpc;
 pub mod basic_wallet;
 pub mod empty_blockchain;
 pub mod non_empty_blockchain;
+pub mod transfer_interaction;
@@ -1,5 +1,5 @@
 use chrono::DateTime;
-use monero::{Address, Amount, Network};
+use monero::{cryptonote::hash::Hashable, Address, Amount, Network};
 use monero_rpc::{BlockHash, BlockHeaderResponse, GetBlockHeaderSelector};
 
 use super::helpers;
@@ -63,21 +63,35 @@ pub async fn run() {
     let last_added_block_header = BlockHeaderResponse {
         // `block_size` is not tested inside the test functions below because it varies
         block_size: 85,
+        // `block_weight` and `long_term_weight` are not tested for the same reason
+        block_weight: 85,
+        // the difficulty is fixed to 1, genesis block included
+        cumulative_difficulty: 61,
         depth: 0,
         difficulty: 1,
         hash: last_added_block_hash,
         height: regtest.get_block_count().await.unwrap().get() - 1,
+        long_term_weight: 85,
         // `*_version` are not tested inside the test functions below because they varies
         major_version: 16,
+        // the coinbase transaction pays a one-time key, so take its hash from the block itself
+        miner_tx_hash: regtest
+            .get_block(GetBlockHeaderSelector::Hash(last_added_block_hash))
+            .await
+            .unwrap()
+            .miner_tx
+            .hash(),
         minor_version: 16,
         nonce: 0,
         num_txes: 0,
         orphan_status: false,
+        pow_hash: None,
         prev_hash: last_but_one_added_block_hash,
         reward: Amount::from_pico(35180379334199),
         // this is not used inside the test functions below, since its value depend on when the
         // test was run, so use any date in this field since it is insignificant for testing.
         timestamp: DateTime::from_timestamp(0, 0).unwrap(),
+        wide_difficulty: 1,
     };
     helpers::regtest::get_last_block_header_assert_block_header(
         &regtest,
@@ -115,12 +129,27 @@ pub async fn run() {
         .await
         .unwrap();
     helpers::regtest::get_block_headers_range_assert_block_headers(
+        &regtest,
+        59..=60,
+        vec![last_but_one_block_header.clone(), last_block_header.clone()],
+    )
+    .await;
+    helpers::regtest::batch_get_block_headers_assert_block_headers(
         &regtest,
         59..=60,
         vec![last_but_one_block_header, last_block_header],
+        current_top_block_height,
     )
     .await;
 
+    helpers::regtest::get_coinbase_tx_sum_assert_sum_of_rewards(&regtest, 59..=60).await;
+    helpers::regtest::get_output_histogram_assert_coinbase_outputs(&regtest, 60).await;
+    helpers::regtest::get_output_distribution_assert_one_output_per_block(&regtest, 59..=60).await;
+
+    helpers::regtest::get_miner_data_assert_tip(&regtest, address_1).await;
+    helpers::regtest::calc_pow_assert_hash(&regtest, address_1).await;
+    helpers::regtest::add_aux_pow_assert_merkle_root(&regtest, address_1).await;
+
     // STEP 3: we test the last function that can modify the blockchain state: `submit_block`.
     // In order for it to work, we just get a block template on which to mine. Since the difficulty
     // of the network is `1`, any correct block template should be accepted by `submit_block`.
@@ -128,4 +157,5 @@ pub async fn run() {
     helpers::regtest::submit_block_assert_ok(&regtest, block_template.blocktemplate_blob).await;
     helpers::regtest::submit_block_error_wrong_block_blob(&regtest).await;
     helpers::regtest::submit_block_error_block_not_accepted(&regtest).await;
+    helpers::regtest::submit_block_assert_mined_with_template_helpers(&regtest, address_1).await;
 }
@@ -0,0 +1,85 @@
+use std::{collections::HashMap, path::Path};
+
+use monero::Amount;
+use monero_rpc::{
+    DaemonRpcClient, RegtestDaemonJsonRpcClient, TransferOptions, TransferPriority, WalletClient,
+};
+
+use super::helpers;
+
+/*
+* A short version of `all_clients_interaction`: a wallet mines some blocks, then pays two other
+* wallets in one transfer.
+*
+* Besides running against docker with the other tests, this scenario is replayed without docker
+* from the fixtures committed under `tests/fixtures`, recorded by running `main_functional_test`
+* with `MONERO_RPC_RECORD_DIR` set. The wallets have random names, their keys are masked in the
+* fixtures and the destinations of the transfer are in the order of a `HashMap`, so the requests
+* never match the recorded ones exactly, and the replay is lenient.
+*/
+
+const SCENARIO: &str = "transfer_interaction";
+
+pub async fn run() {
+    let (regtest, daemon_rpc, wallet) = helpers::setup_monero_with_fixtures(SCENARIO);
+    interact(regtest, daemon_rpc, wallet).await;
+}
+
+pub async fn replay() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let (regtest, daemon_rpc, wallet) = helpers::replay_monero(SCENARIO, &fixtures);
+    interact(regtest, daemon_rpc, wallet).await;
+}
+
+async fn interact(
+    regtest: RegtestDaemonJsonRpcClient,
+    daemon_rpc: DaemonRpcClient,
+    wallet: WalletClient,
+) {
+    // STEP 1: create the wallets and get their addresses
+    let mut addresses = vec![];
+    let mut wallet_names = vec![];
+    for _ in 0..3 {
+        let wallet_name =
+            helpers::wallet::create_wallet_with_empty_password_assert_ok(&wallet).await;
+        let key_pair = helpers::wallet::query_key_pair(&wallet).await;
+        addresses.push(helpers::wallet::get_address_assert_key_pair(&wallet, &key_pair).await);
+        wallet_names.push(wallet_name);
+    }
+
+    // STEP 2: mine enough blocks to the first wallet for its first reward to unlock
+    helpers::wallet::open_wallet_with_no_or_empty_password_assert_ok(&wallet, &wallet_names[0])
+        .await;
+    helpers::regtest::generate_blocks_assert_ok(&regtest, 80, addresses[0]).await;
+    helpers::wallet::refresh_assert_received_money(&wallet, None, true).await;
+    let balance_before = wallet.get_balance(0, None).await.unwrap();
+    assert!(balance_before.unlocked_balance > Amount::from_xmr(3.0).unwrap());
+
+    // STEP 3: pay the two other wallets, and check the transaction is in the pool of the daemon
+    let destinations = HashMap::from([
+        (addresses[1], Amount::from_xmr(1.0).unwrap()),
+        (addresses[2], Amount::from_xmr(2.0).unwrap()),
+    ]);
+    let transfer = helpers::wallet::transfer_assert_ok(
+        &wallet,
+        destinations,
+        TransferOptions::default(),
+        TransferPriority::Default,
+    )
+    .await;
+
+    let transactions = daemon_rpc
+        .get_transactions(vec![transfer.tx_hash.0], None, None)
+        .await
+        .unwrap();
+    let txs = transactions.txs.unwrap();
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].tx_hash, transfer.tx_hash);
+    assert!(txs[0].in_pool);
+
+    let balance_after = wallet.get_balance(0, None).await.unwrap();
+    assert_eq!(
+        balance_after.balance,
+        balance_before.balance - transfer.amount - transfer.fee
+    );
+}
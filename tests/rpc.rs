@@ -51,7 +51,8 @@ async fn main_functional_test() {
      *    blocks, so the other tests would not work if running at the same time
      *    `all_clients_interaction` runs. Also, it makes sense `all_clients_interaction` to
      *    run last because the other tests test each client individually, but `all_clients_interaction`
-     *    calls functions from all clients.
+     *    calls functions from all clients. `transfer_interaction` runs after it for the same
+     *    reasons.
      *
      */
 
@@ -68,6 +69,43 @@ async fn main_functional_test() {
     res.unwrap();
 
     clients_tests::all_clients_interaction::run().await;
+    clients_tests::transfer_interaction::run().await;
+}
+
+// Replays the session of `transfer_interaction` committed under `tests/fixtures`, without docker.
+#[tokio::test]
+async fn replay_transfer_interaction() {
+    clients_tests::transfer_interaction::replay().await;
+}
+
+// The first address does not point to any daemon, so every call has to fail over to the second
+// one.
+#[tokio::test]
+async fn test_daemon_failover() {
+    let dhost = std::env::var("MONERO_DAEMON_HOST").unwrap_or_else(|_| "localhost".into());
+    let daemon = monero_rpc::RpcClientBuilder::new()
+        .prefer_highest_height(true)
+        .build_with_failover([
+            "http://127.0.0.1:1".to_string(),
+            format!("http://{}:18081", dhost),
+        ])
+        .unwrap()
+        .daemon();
+
+    assert!(daemon.get_block_count().await.is_ok());
+
+    let nodes = daemon.nodes();
+    assert!(!nodes[0].healthy);
+    assert!(nodes[0].last_error.is_some());
+    assert!(nodes[1].healthy);
+    assert!(nodes[1].active);
+
+    let nodes = daemon.health_check().await;
+    assert!(!nodes[0].healthy);
+    assert_eq!(nodes[0].height, None);
+    assert!(nodes[1].height.is_some());
+    assert!(nodes[1].version.is_some());
+    assert!(nodes[1].active);
 }
 
 // Authentication tests need to run against a monero daemon and wallet rpc with